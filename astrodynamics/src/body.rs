@@ -0,0 +1,461 @@
+#![allow(dead_code)]
+#![allow(unused_doc_comments)]
+
+/**
+ * body.rs contains the Body struct and implements methods for it. A body
+ * struct contains only the position and velocity vectors of the body,
+ * other parameters are calculated using methods. A body is instantiated
+ * using using the Body::new() method, which also determines the type of
+ * orbit the body has at the same time. orbit_type does not have to be
+ * given manually.
+ */
+use nalgebra::{Matrix3, Vector3};
+use std::f64::consts::PI;
+
+use super::error::OrbitError;
+use super::kepler::{solve_elliptic, solve_hyperbolic, SolverSettings};
+use super::units::{GravParam, Length, Position, Time, Velocity};
+
+const PI2: f64 = 2.0 * PI;
+
+/**
+ * OrbitType is used to abstract away some of the functions that depend on
+ * the type of orbit the body is in, like kepler's equation. That way, you
+ * can call one function and it will return the correct value
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OrbitType {
+    Circular,
+    Elliptic,
+    Parabolic,
+    Hyperbolic,
+}
+
+impl OrbitType {
+    /// Return the orbit type given the eccentricity, Body::new
+    /// uses this function to set the orbit type when an instance
+    /// is constructed
+    pub fn new(eccentricity: f64) -> OrbitType {
+        if eccentricity == 0.0 {
+            OrbitType::Circular
+        } else if eccentricity < 1.0 && eccentricity > 0.0 {
+            OrbitType::Elliptic
+        } else if eccentricity == 1.0 {
+            OrbitType::Parabolic
+        } else {
+            OrbitType::Hyperbolic
+        }
+    }
+}
+
+/// Main structure everything in this file relies on. position and velocity
+/// are measured from the central body, whose gravitational parameter is mu
+#[derive(Debug, Clone)]
+pub struct Body {
+    pub position: Vector3<f64>,
+    pub velocity: Vector3<f64>,
+    pub mu: f64,
+    pub orbit_type: OrbitType,
+}
+
+/* Adds methods to Body struct */
+impl Body {
+    /// Create a body orbiting a central body with gravitational parameter mu,
+    /// e.g. constants::SOLARGM for a heliocentric state
+    pub fn new(position: Vector3<f64>, velocity: Vector3<f64>, mu: f64) -> Body {
+        // h and e are used for determining what kind of orbit the body is currently in
+        let h = position.cross(&velocity);
+        let e = ((velocity.cross(&h) / mu) - position.normalize()).norm();
+        Body {
+            position,
+            velocity,
+            mu,
+            orbit_type: OrbitType::new(e),
+        }
+    }
+
+    /// Unit-safe version of Body::new
+    pub fn from_state(position: Position, velocity: Velocity, mu: GravParam) -> Body {
+        Body::new(
+            position.to_au(),
+            velocity.to_au_per_day(),
+            mu.to_au3_per_day2(),
+        )
+    }
+
+    /// Current position and velocity as unit-safe quantities
+    pub fn state(&self) -> (Position, Velocity) {
+        (
+            Position::au(self.position),
+            Velocity::au_per_day(self.velocity),
+        )
+    }
+
+    pub fn gravitational_parameter(&self) -> GravParam {
+        GravParam::au3_per_day2(self.mu)
+    }
+
+    pub fn semi_major_axis_length(&self) -> Length {
+        Length::au(self.semi_major_axis())
+    }
+
+    pub fn period(&self) -> Time {
+        Time::days(self.orbital_period())
+    }
+
+    /// Unit-safe version of position_at_time
+    pub fn position_after(&self, time: Time) -> Result<Position, OrbitError> {
+        Ok(Position::au(self.position_at_time(time.to_days())?))
+    }
+
+    /// Unit-safe version of velocity_at_time
+    pub fn velocity_after(&self, time: Time) -> Result<Velocity, OrbitError> {
+        Ok(Velocity::au_per_day(self.velocity_at_time(time.to_days())?))
+    }
+
+    /// Re-express this body's state relative to another body, e.g. a moon's
+    /// heliocentric state relative to its planet. mu is the gravitational
+    /// parameter of the new central body
+    pub fn relative_to(&self, primary: &Body, mu: f64) -> Body {
+        Body::new(
+            self.position - primary.position,
+            self.velocity - primary.velocity,
+            mu,
+        )
+    }
+
+    pub fn radial_velocity(&self) -> Vector3<f64> {
+        (self.velocity.dot(&self.position) / self.position.norm_squared()) * self.position
+    }
+
+    pub fn tangential_velocity(&self) -> Vector3<f64> {
+        self.omega().cross(&self.position)
+    }
+
+    pub fn true_anomaly(&self) -> f64 {
+        let e_vec = self.eccentricity_vector();
+        let posit = self.position.normalize();
+        let val = safe_acos(e_vec.dot(&posit) / (e_vec.norm() * posit.norm()));
+        if posit.dot(&self.velocity.normalize()) < 0.0 {
+            PI2 - val
+        } else {
+            val
+        }
+    }
+
+    /// Position vector at a time in the future, starting from now. Goes
+    /// through the universal-variable propagator so it works for every
+    /// orbit type, not just elliptic ones
+    pub fn position_at_time(&self, time: f64) -> Result<Vector3<f64>, OrbitError> {
+        Ok(self.state_at_time(time)?.0)
+    }
+
+    /// Velocity vector at a time in the future, starting from now
+    pub fn velocity_at_time(&self, time: f64) -> Result<Vector3<f64>, OrbitError> {
+        Ok(self.state_at_time(time)?.1)
+    }
+
+    /// Position and velocity at a true anomaly, rotated back into the
+    /// frame the body's current state is expressed in
+    pub fn position_and_velocity(&self, angle: f64) -> (Vector3<f64>, Vector3<f64>) {
+        let r = self.position_at_angle(angle);
+        let v = self.velocity_at_angle(angle);
+        let tht = angle - self.true_anomaly();
+        let trans = Matrix3::from_rows(&[
+            Vector3::new(tht.cos(), -tht.sin(), 0.0).transpose(),
+            Vector3::new(tht.sin(), tht.cos(), 0.0).transpose(),
+            Vector3::new(0.0, 0.0, 1.0).transpose(),
+        ]);
+        (trans * r, trans * v)
+    }
+
+    pub fn position_at_angle(&self, angle: f64) -> Vector3<f64> {
+        let e = self.eccentricity();
+        let p = self.orbital_parameter();
+        let radius = p / (1.0 + e * angle.cos());
+        Vector3::new(radius, 0.0, 0.0)
+    }
+
+    pub fn velocity_at_angle(&self, angle: f64) -> Vector3<f64> {
+        let t_frame = self.make_frame();
+        let h = t_frame * self.angular_momentum();
+        let coeff = h.norm() / self.orbital_parameter();
+        let e = self.eccentricity();
+        Vector3::new(
+            coeff * -e * angle.sin(),
+            coeff * (1.0 + e * angle.cos()),
+            0.0,
+        )
+    }
+
+    /* points from focus to perigee if I'm not mistaken */
+    pub fn eccentricity_vector(&self) -> Vector3<f64> {
+        let veloc = self.velocity;
+        let posit = self.position;
+        let h = self.angular_momentum();
+        (veloc.cross(&h) / self.mu) - posit.normalize()
+    }
+
+    pub fn angular_momentum(&self) -> Vector3<f64> {
+        self.position.cross(&self.velocity)
+    }
+
+    pub fn total_energy(&self) -> f64 {
+        let posit = self.position.norm();
+        let veloc = self.velocity.norm();
+        0.5 * veloc.powi(2) - (self.mu / posit)
+    }
+
+    pub fn omega(&self) -> Vector3<f64> {
+        self.angular_momentum() / self.position.norm_squared()
+    }
+
+    pub fn frame_rotation_rate(&self) -> f64 {
+        self.omega().norm()
+    }
+
+    // Relative velocity between two bodies
+    pub fn relative_velocity(&self, other: &Body) -> Vector3<f64> {
+        let total_omega = self.omega() + other.omega();
+        self.velocity - total_omega.cross(&self.position)
+    }
+
+    // Angle to other body
+    pub fn angle_to(&self, other: &Body) -> f64 {
+        let coeff = self.position.cross(&other.position).norm();
+        let dot = self.position.dot(&other.position);
+        coeff.atan2(dot)
+    }
+
+    /* Return a transformation matrix constructed from body's orbit in inertial
+     * frame */
+    pub fn make_frame(&self) -> Matrix3<f64> {
+        let e_zi = self.position.normalize();
+        let e_zeta = self.angular_momentum().normalize();
+        let e_eta = e_zeta.cross(&e_zi);
+        Matrix3::from_rows(&[e_zi.transpose(), e_eta.transpose(), e_zeta.transpose()])
+    }
+
+    pub fn semi_major_axis(&self) -> f64 {
+        let ang_moment = self.angular_momentum().norm();
+        let e = self.eccentricity();
+        ang_moment.powi(2) / (self.mu * (1_f64 - e.powi(2)))
+    }
+
+    pub fn orbital_period(&self) -> f64 {
+        PI2 * (self.semi_major_axis().powi(3) / self.mu).sqrt()
+    }
+
+    pub fn orbital_parameter(&self) -> f64 {
+        let e = self.eccentricity();
+        self.semi_major_axis() * (1.0 - e.powi(2))
+    }
+
+    /// Eccentric anomaly for elliptic orbits, hyperbolic anomaly for
+    /// hyperbolic ones
+    pub fn eccentric_anomaly(&self) -> f64 {
+        let e = self.eccentricity();
+        let theta = self.true_anomaly();
+        match self.orbit_type {
+            OrbitType::Hyperbolic => {
+                let f = ((theta.cos() + e) / (1.0 + e * theta.cos())).acosh();
+                if theta > PI {
+                    -f
+                } else {
+                    f
+                }
+            }
+            _ => 2.0 * ((theta / 2.0).tan() / ((1.0 + e) / (1.0 - e)).sqrt()).atan(),
+        }
+    }
+
+    pub fn time_since_periapsis(&self) -> f64 {
+        let a = self.semi_major_axis();
+        let e = self.eccentricity();
+        match self.orbit_type {
+            OrbitType::Hyperbolic => {
+                let f = self.eccentric_anomaly();
+                (-a.powi(3) / self.mu).sqrt() * (e * f.sinh() - f)
+            }
+            _ => {
+                let e_anom = self.true_to_eccentric(self.true_anomaly());
+                (a.powi(3) / self.mu).sqrt() * (e_anom - e * e_anom.sin())
+            }
+        }
+    }
+
+    pub fn eccentricity(&self) -> f64 {
+        self.eccentricity_vector().norm()
+    }
+
+    pub fn inclination(&self) -> f64 {
+        let h = self.angular_momentum();
+        safe_acos(h[2] / h.norm()) // h[2] is the z component of the vector
+    }
+
+    pub fn ascending_node(&self) -> Vector3<f64> {
+        let k = Vector3::new(0.0, 0.0, 1.0);
+        k.cross(&self.angular_momentum())
+    }
+
+    pub fn argument_of_periapsis(&self) -> f64 {
+        let n = self.ascending_node();
+        let e = self.eccentricity_vector();
+        let omega = safe_acos(n.dot(&e) / (n.norm() * e.norm()));
+        if e[2] < 0.0 {
+            PI2 - omega
+        } else {
+            omega
+        }
+    }
+
+    pub fn argument_of_ascending_node(&self) -> f64 {
+        let n = self.ascending_node();
+        let n_x = n[0];
+        let n_y = n[1];
+        if n_y >= 0.0 {
+            safe_acos(n_x / n.norm())
+        } else {
+            PI2 - safe_acos(n_x / n.norm())
+        }
+    }
+
+    pub fn true_to_eccentric(&self, t_anom: f64) -> f64 {
+        let a = self.semi_major_axis();
+        let e = self.eccentricity();
+        let b = a * (1.0 - e.powi(2)).sqrt();
+        let p = self.orbital_parameter();
+        let r = p / (1.0 + e * t_anom.cos());
+        let c = (a * e + r * t_anom.cos()) / a;
+        let s = (r / b) * t_anom.sin();
+        s.atan2(c)
+    }
+
+    /// True anomaly at a time in the future, starting from now
+    pub fn true_anomaly_at_time(&self, time: f64) -> Result<f64, OrbitError> {
+        match self.orbit_type {
+            // No eccentric anomaly to go through, so propagate the state instead
+            OrbitType::Parabolic => Ok(self.at_time(time)?.true_anomaly()),
+            _ => {
+                let angle = self.eccentric_anomaly_at_time(time)?;
+                Ok(self.eccentric_to_true_anomaly(angle))
+            }
+        }
+    }
+
+    /// The eccentric anomaly at a time in the future, starting from now
+    pub fn eccentric_anomaly_at_time(&self, time: f64) -> Result<f64, OrbitError> {
+        let m_anom = self.mean_anomaly(time + self.time_since_periapsis());
+        self.eccentric_from_mean(m_anom)
+    }
+
+    /// The eccentric anomaly at a certain time
+    pub fn eccentric_from_mean(&self, m_anom: f64) -> Result<f64, OrbitError> {
+        self.kepler(m_anom)
+    }
+
+    /// Return the eccentric anomaly using the appropriate Kepler equation
+    pub fn kepler(&self, m_anom: f64) -> Result<f64, OrbitError> {
+        let e = self.eccentricity();
+        let settings = SolverSettings::default();
+        let solution = match self.orbit_type {
+            // e = 0 just gives E = M, the elliptic solver handles it fine
+            OrbitType::Circular | OrbitType::Elliptic => solve_elliptic(m_anom, e, &settings),
+            OrbitType::Hyperbolic => solve_hyperbolic(m_anom, e, &settings),
+            OrbitType::Parabolic => return Err(OrbitError::Parabolic),
+        };
+        solution.into_result()
+    }
+
+    /// True anomaly in [0, 2 pi) from the eccentric (or hyperbolic) anomaly
+    pub fn eccentric_to_true_anomaly(&self, e_anom: f64) -> f64 {
+        let e = self.eccentricity();
+        let t_anom = match self.orbit_type {
+            OrbitType::Hyperbolic => {
+                2.0 * (((e + 1.0) / (e - 1.0)).sqrt() * (e_anom / 2.0).tanh()).atan()
+            }
+            _ => 2.0 * (((1.0 + e) / (1.0 - e)).sqrt() * (e_anom / 2.0).tan()).atan(),
+        };
+        t_anom.rem_euclid(PI2)
+    }
+
+    /// Return the mean anomaly at a certain time from current position
+    pub fn mean_anomaly(&self, t: f64) -> f64 {
+        let n = (self.mu / self.semi_major_axis().abs().powi(3)).sqrt();
+        n * t
+    }
+
+    pub fn distance_to(&self, other: &Body) -> f64 {
+        let arg_of_peri = self.argument_of_periapsis();
+        let inc = self.inclination();
+        let arg_of_an = self.argument_of_ascending_node();
+        let t_matrix = three_one_three_transform(arg_of_peri, inc, arg_of_an);
+        let d_vec = t_matrix * other.position - t_matrix * self.position;
+        d_vec.norm().abs()
+    }
+}
+
+/* acos that doesn't turn round-off like 1.0000000000000002 into NaN */
+fn safe_acos(val: f64) -> f64 {
+    val.clamp(-1.0, 1.0).acos()
+}
+
+pub fn three_one_three_transform(
+    arg_of_peri: f64,
+    inclination: f64,
+    arg_of_an: f64,
+) -> Matrix3<f64> {
+    let omega = arg_of_peri;
+    let inc = inclination;
+    let tht = arg_of_an;
+    let m_c = Matrix3::new(
+        omega.cos(),
+        omega.sin(),
+        0.0,
+        -omega.sin(),
+        omega.cos(),
+        0.0,
+        0.0,
+        0.0,
+        1.0,
+    );
+    let m_b = Matrix3::new(
+        1.0,
+        0.0,
+        0.0,
+        0.0,
+        inc.cos(),
+        inc.sin(),
+        0.0,
+        -inc.sin(),
+        inc.cos(),
+    );
+    let m_a = Matrix3::new(
+        tht.cos(),
+        tht.sin(),
+        0.0,
+        -tht.sin(),
+        tht.cos(),
+        0.0,
+        0.0,
+        0.0,
+        1.0,
+    );
+
+    m_c * m_b * m_a
+}
+
+/* create a transform matrix given an array of unit vectors for two frames */
+pub fn transform(frame_one: [Vector3<f64>; 3], frame_two: [Vector3<f64>; 3]) -> Matrix3<f64> {
+    Matrix3::new(
+        frame_two[0].dot(&frame_one[0]),
+        frame_two[1].dot(&frame_one[0]),
+        frame_two[2].dot(&frame_one[0]),
+        frame_two[0].dot(&frame_one[1]),
+        frame_two[1].dot(&frame_one[1]),
+        frame_two[2].dot(&frame_one[1]),
+        frame_two[0].dot(&frame_one[2]),
+        frame_two[1].dot(&frame_one[2]),
+        frame_two[2].dot(&frame_one[2]),
+    )
+}
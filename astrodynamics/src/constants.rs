@@ -0,0 +1,33 @@
+/*!
+ * Gravitational parameters of the Sun and planets. Everything in the
+ * library works in AU and days, so the planetary values (taken from the
+ * same km^3/s^2 table hw1 uses) are converted on the way in.
+ */
+
+use super::units::{AUTOKM, DAYTOSEC};
+
+/// Multiply a gravitational parameter in km^3/s^2 by this to get AU^3/day^2
+pub const KM3S2TOAU3DAY2: f64 = (DAYTOSEC * DAYTOSEC) / (AUTOKM * AUTOKM * AUTOKM);
+
+/// Sun's gravitational parameter in AU^3/day^2
+pub const SOLARGM: f64 = 2.963092749241593e-4;
+
+pub const MERCURYGM: f64 = 22032.09 * KM3S2TOAU3DAY2;
+pub const VENUSGM: f64 = 324858.63 * KM3S2TOAU3DAY2;
+pub const EARTHGM: f64 = 398600.440 * KM3S2TOAU3DAY2;
+pub const MARSGM: f64 = 42828.3 * KM3S2TOAU3DAY2;
+pub const JUPITERGM: f64 = 1.26686511e8 * KM3S2TOAU3DAY2;
+pub const SATURNGM: f64 = 3.79312078e7 * KM3S2TOAU3DAY2;
+pub const URANUSGM: f64 = 5.793966e6 * KM3S2TOAU3DAY2;
+pub const NEPTUNEGM: f64 = 6.835107e6 * KM3S2TOAU3DAY2;
+pub const PLUTOGM: f64 = 872.4 * KM3S2TOAU3DAY2;
+pub const MOONGM: f64 = 4902.800066 * KM3S2TOAU3DAY2;
+
+/// Earth's equatorial radius in km, the reference radius EGM-96's
+/// harmonics are normalized to (not the mean radius in Planet::earth)
+pub const EARTHEQUATORIALRADIUS: f64 = 6378.1363;
+
+/// Earth's zonal harmonics (EGM-96, unnormalized), for the oblateness model
+pub const EARTHJ2: f64 = 1.08262668e-3;
+pub const EARTHJ3: f64 = -2.53265649e-6;
+pub const EARTHJ4: f64 = -1.61962159e-6;
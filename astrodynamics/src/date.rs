@@ -1,4 +1,4 @@
-/**
+/*!
  * Handles julian and gregorian date stuff
  */
 
@@ -54,7 +54,7 @@ pub fn julian_to_greg(julian: f64) -> Gregorian {
         i += 1;
     }
     let month = i as f64;
-    let (l_month_sum, _) = l_months.split_at(i - 1 as usize);
+    let (l_month_sum, _) = l_months.split_at(i - 1);
     let m_sum: f64 = l_month_sum.iter().sum();
     let day: f64 = day_of_year - m_sum;
     let tau: f64 = (days - day_of_year) * 24.0;
@@ -67,6 +67,6 @@ pub fn julian_to_greg(julian: f64) -> Gregorian {
         day: day as u32,
         hour: hour as u32,
         minute: minute as u32,
-        second: second,
+        second,
     }
 }
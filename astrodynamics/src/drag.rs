@@ -0,0 +1,436 @@
+/*!
+ * Atmospheric drag and orbit lifetime. The atmosphere turns with the
+ * planet, so drag acts along the velocity relative to the rotating air,
+ * a = -1/2 rho |v_rel| v_rel / B, with B = m / (Cd A) the ballistic
+ * coefficient in kg/m^2. Like the oblateness model this assumes the state
+ * is in the planet's equator frame, and it treats the planet as a sphere
+ * when working out altitude.
+ *
+ * Two density models are available:
+ *   - Vallado's piecewise exponential atmosphere (table 8-4), a base
+ *     density and scale height for each band from sea level to 1000 km
+ *   - the US Standard Atmosphere 1976, worked out exactly from its
+ *     temperature layers up to 86 km and interpolated log-linearly in its
+ *     published densities above that
+ */
+use nalgebra::Vector3;
+
+use super::body::Body;
+use super::error::OrbitError;
+use super::force::ForceModel;
+use super::integrator::{integrate, pack, unpack, IntegratorSettings};
+use super::planet::Planet;
+use super::units::AUTOM;
+
+/* Base altitude (km), base density (kg/m^3) and scale height (km) */
+const EXPONENTIAL: [(f64, f64, f64); 28] = [
+    (0.0, 1.225, 7.249),
+    (25.0, 3.899e-2, 6.349),
+    (30.0, 1.774e-2, 6.682),
+    (40.0, 3.972e-3, 7.554),
+    (50.0, 1.057e-3, 8.382),
+    (60.0, 3.206e-4, 7.714),
+    (70.0, 8.770e-5, 6.549),
+    (80.0, 1.905e-5, 5.799),
+    (90.0, 3.396e-6, 5.382),
+    (100.0, 5.297e-7, 5.877),
+    (110.0, 9.661e-8, 7.263),
+    (120.0, 2.438e-8, 9.473),
+    (130.0, 8.484e-9, 12.636),
+    (140.0, 3.845e-9, 16.149),
+    (150.0, 2.070e-9, 22.523),
+    (180.0, 5.464e-10, 29.740),
+    (200.0, 2.789e-10, 37.105),
+    (250.0, 7.248e-11, 45.546),
+    (300.0, 2.418e-11, 53.628),
+    (350.0, 9.518e-12, 53.298),
+    (400.0, 3.725e-12, 58.515),
+    (450.0, 1.585e-12, 60.828),
+    (500.0, 6.967e-13, 63.822),
+    (600.0, 1.454e-13, 71.835),
+    (700.0, 3.614e-14, 88.667),
+    (800.0, 1.170e-14, 124.64),
+    (900.0, 5.245e-15, 181.05),
+    (1000.0, 3.019e-15, 268.00),
+];
+
+/* US 1976 layers below 86 km: base geopotential altitude (km) and lapse
+ * rate (K/km) */
+const LAYERS: [(f64, f64); 7] = [
+    (0.0, -6.5),
+    (11.0, 0.0),
+    (20.0, 1.0),
+    (32.0, 2.8),
+    (47.0, 0.0),
+    (51.0, -2.8),
+    (71.0, -2.0),
+];
+const SEA_LEVEL_TEMPERATURE: f64 = 288.15;
+const SEA_LEVEL_PRESSURE: f64 = 101_325.0;
+/* g0 M0 / R* in K/km, and R* / M0 in J/(kg K) */
+const HYDROSTATIC: f64 = 34.163_195;
+const GAS_CONSTANT: f64 = 287.053_07;
+/* Earth radius used for geopotential altitude, km */
+const GEOPOTENTIAL_RADIUS: f64 = 6356.766;
+
+/* US 1976 densities above 86 km: geometric altitude (km) and density
+ * (kg/m^3) */
+const US76: [(f64, f64); 21] = [
+    (86.0, 6.958e-6),
+    (90.0, 3.416e-6),
+    (100.0, 5.604e-7),
+    (110.0, 9.708e-8),
+    (120.0, 2.222e-8),
+    (130.0, 8.152e-9),
+    (140.0, 3.831e-9),
+    (150.0, 2.076e-9),
+    (160.0, 1.233e-9),
+    (180.0, 5.194e-10),
+    (200.0, 2.541e-10),
+    (250.0, 6.073e-11),
+    (300.0, 1.916e-11),
+    (350.0, 6.967e-12),
+    (400.0, 2.803e-12),
+    (450.0, 1.184e-12),
+    (500.0, 5.215e-13),
+    (600.0, 1.137e-13),
+    (700.0, 3.070e-14),
+    (800.0, 1.136e-14),
+    (900.0, 5.759e-15),
+];
+
+/* Lifetime crossing time is refined until it's this close, days */
+const TOLERANCE: f64 = 1e-6;
+const MAX_ITERATIONS: usize = 100;
+
+/// Density model for the atmosphere, both for Earth
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Atmosphere {
+    Exponential,
+    Us76,
+}
+
+impl Atmosphere {
+    /// Density in kg/m^3 at a geometric altitude in km. Below sea level is
+    /// sea level; above the top of the model the density keeps falling off
+    /// at the model's last scale height.
+    pub fn density(self, altitude: f64) -> f64 {
+        let altitude = altitude.max(0.0);
+        match self {
+            Atmosphere::Exponential => {
+                let (base, density, scale) = EXPONENTIAL
+                    .iter()
+                    .rev()
+                    .find(|(base, _, _)| altitude >= *base)
+                    .cloned()
+                    .unwrap_or(EXPONENTIAL[0]);
+                density * (-(altitude - base) / scale).exp()
+            }
+            Atmosphere::Us76 if altitude < US76[0].0 => us76_layers(altitude),
+            Atmosphere::Us76 => {
+                let index = US76
+                    .iter()
+                    .rposition(|(base, _)| altitude >= *base)
+                    .unwrap_or(0)
+                    .min(US76.len() - 2);
+                let (h_0, rho_0) = US76[index];
+                let (h_1, rho_1) = US76[index + 1];
+                // Log-linear, so past the last entry it extrapolates along
+                // the last scale height
+                let scale = (h_1 - h_0) / (rho_0 / rho_1).ln();
+                rho_0 * (-(altitude - h_0) / scale).exp()
+            }
+        }
+    }
+}
+
+/* US 1976 density below 86 km from its temperature layers. Temperature
+ * changes linearly with geopotential altitude in each layer, and pressure
+ * follows from hydrostatic equilibrium. */
+fn us76_layers(altitude: f64) -> f64 {
+    let geopotential = GEOPOTENTIAL_RADIUS * altitude / (GEOPOTENTIAL_RADIUS + altitude);
+    let mut temperature = SEA_LEVEL_TEMPERATURE;
+    let mut pressure = SEA_LEVEL_PRESSURE;
+    for (i, &(base, lapse)) in LAYERS.iter().enumerate() {
+        let top = LAYERS.get(i + 1).map_or(f64::INFINITY, |layer| layer.0);
+        let height = geopotential.min(top) - base;
+        if lapse == 0.0 {
+            pressure *= (-HYDROSTATIC * height / temperature).exp();
+        } else {
+            let next = temperature + lapse * height;
+            pressure *= (temperature / next).powf(HYDROSTATIC / lapse);
+            temperature = next;
+        }
+        if geopotential <= top {
+            break;
+        }
+    }
+    pressure / (GAS_CONSTANT * temperature)
+}
+
+/// Drag from a planet's atmosphere, which turns with the planet
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Drag {
+    pub atmosphere: Atmosphere,
+    /// m / (Cd A), kg/m^2
+    pub ballistic_coefficient: f64,
+    /// Radius altitude is measured from, AU
+    pub radius: f64,
+    /// Rotation rate of the atmosphere about z, rad/day
+    pub rotation_rate: f64,
+}
+
+impl Drag {
+    pub fn new(planet: &Planet, atmosphere: Atmosphere, ballistic_coefficient: f64) -> Drag {
+        Drag {
+            atmosphere,
+            ballistic_coefficient,
+            radius: planet.radius,
+            rotation_rate: planet.rotation_rate(),
+        }
+    }
+
+    /// Earth's atmosphere on a spacecraft with a ballistic coefficient
+    pub fn earth(atmosphere: Atmosphere, ballistic_coefficient: f64) -> Drag {
+        Drag::new(&Planet::earth(), atmosphere, ballistic_coefficient)
+    }
+
+    /// Altitude above the planet, AU
+    pub fn altitude(&self, position: &Vector3<f64>) -> f64 {
+        position.norm() - self.radius
+    }
+}
+
+impl ForceModel for Drag {
+    fn acceleration(
+        &self,
+        _: f64,
+        position: &Vector3<f64>,
+        velocity: &Vector3<f64>,
+    ) -> Vector3<f64> {
+        let density = self
+            .atmosphere
+            .density(self.altitude(position) * AUTOM / 1000.0);
+        let relative = velocity - (self.rotation_rate * Vector3::z()).cross(position);
+        // rho / B is per meter, so AUTOM brings it to per AU
+        -0.5 * density / self.ballistic_coefficient * AUTOM * relative.norm() * relative
+    }
+}
+
+/// When and where an orbit's periapsis drops below the reentry altitude
+#[derive(Debug, Clone)]
+pub struct Lifetime {
+    /// Days from the start
+    pub time: f64,
+    pub state: Body,
+}
+
+/// How long until the osculating periapsis altitude of a body falls below
+/// reentry_altitude, under a force model that should include the central
+/// body's gravity and drag. Altitudes are above radius, all in AU. None if
+/// it's still up after max_duration days.
+pub fn lifetime<M: ForceModel>(
+    body: &Body,
+    model: &M,
+    radius: f64,
+    reentry_altitude: f64,
+    max_duration: f64,
+    settings: &IntegratorSettings,
+) -> Result<Option<Lifetime>, OrbitError> {
+    let periapsis_altitude = |state: &Body| {
+        let e = state.eccentricity();
+        if e >= 1.0 {
+            return f64::INFINITY;
+        }
+        state.semi_major_axis() * (1.0 - e) - radius
+    };
+    if periapsis_altitude(body) < reentry_altitude {
+        return Ok(Some(Lifetime {
+            time: 0.0,
+            state: body.clone(),
+        }));
+    }
+
+    let mut time = 0.0;
+    let mut state = body.clone();
+    while time < max_duration {
+        // One orbit at a time, so a long lifetime doesn't keep every step
+        // of the whole decay around
+        let chunk = if periapsis_altitude(&state).is_finite() {
+            state.orbital_period()
+        } else {
+            max_duration
+        };
+        let end = (time + chunk).min(max_duration);
+        let start = time;
+        let solution = integrate(
+            |t, y| {
+                let (position, velocity) = unpack(y);
+                let acceleration = model.acceleration(start + t, &position, &velocity);
+                pack(&velocity, &acceleration)
+            },
+            0.0,
+            pack(&state.position, &state.velocity),
+            end - start,
+            settings,
+        )?;
+        let state_at = |t: f64| {
+            solution.state_at(t - start).map(|y| {
+                let (position, velocity) = unpack(&y);
+                Body::new(position, velocity, body.mu)
+            })
+        };
+
+        let mut before = start;
+        for after in solution.times().skip(1).map(|t| start + t) {
+            let after_state = state_at(after).ok_or(OrbitError::DegenerateState)?;
+            if periapsis_altitude(&after_state) < reentry_altitude {
+                let (mut lower, mut upper) = (before, after);
+                for _ in 0..MAX_ITERATIONS {
+                    if upper - lower < TOLERANCE {
+                        break;
+                    }
+                    let middle = (lower + upper) / 2.0;
+                    let middle_state = state_at(middle).ok_or(OrbitError::DegenerateState)?;
+                    if periapsis_altitude(&middle_state) < reentry_altitude {
+                        upper = middle;
+                    } else {
+                        lower = middle;
+                    }
+                }
+                return Ok(Some(Lifetime {
+                    time: upper,
+                    state: state_at(upper).ok_or(OrbitError::DegenerateState)?,
+                }));
+            }
+            before = after;
+        }
+
+        time = end;
+        state = state_at(end).ok_or(OrbitError::DegenerateState)?;
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::force::TwoBody;
+    use crate::integrator::Method;
+    use crate::units::Length;
+
+    /* Geometric altitude, km, of a geopotential altitude */
+    fn geometric(geopotential: f64) -> f64 {
+        GEOPOTENTIAL_RADIUS * geopotential / (GEOPOTENTIAL_RADIUS - geopotential)
+    }
+
+    fn relative_error(value: f64, expected: f64) -> f64 {
+        ((value - expected) / expected).abs()
+    }
+
+    #[test]
+    fn us76_density_at_layer_bases() {
+        // Published US 1976 densities at the base of each layer
+        let bases = [
+            (0.0, 1.2250),
+            (11.0, 3.6392e-1),
+            (20.0, 8.8035e-2),
+            (32.0, 1.3225e-2),
+            (47.0, 1.4275e-3),
+            (51.0, 8.6160e-4),
+            (71.0, 6.4211e-5),
+        ];
+        for &(geopotential, expected) in bases.iter() {
+            let density = Atmosphere::Us76.density(geometric(geopotential));
+            assert!(
+                relative_error(density, expected) < 1e-4,
+                "{} km: {} vs {}",
+                geopotential,
+                density,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn us76_density_at_table_entries() {
+        for &(altitude, expected) in US76.iter().skip(1) {
+            let density = Atmosphere::Us76.density(altitude);
+            assert!(relative_error(density, expected) < 1e-12, "{} km", altitude);
+        }
+        // The layers and the table meet at 86 km
+        let below = Atmosphere::Us76.density(US76[0].0 - 1e-9);
+        let above = Atmosphere::Us76.density(US76[0].0);
+        assert!(
+            relative_error(below, above) < 1e-3,
+            "{} vs {}",
+            below,
+            above
+        );
+        // Density keeps falling above the table
+        assert!(Atmosphere::Us76.density(1000.0) < US76[US76.len() - 1].1);
+    }
+
+    #[test]
+    fn lifetime_shortens_with_area_to_mass() {
+        let planet = Planet::earth();
+        let radius = planet.radius;
+        let altitude = |km: f64| Length::km(km).to_au();
+        let start = radius + altitude(220.0);
+        let speed = (planet.gm / start).sqrt();
+        let body = Body::new(
+            Vector3::new(start, 0.0, 0.0),
+            Vector3::new(0.0, speed, 0.0),
+            planet.gm,
+        );
+        let settings = IntegratorSettings {
+            tolerance: 1e-10,
+            ..IntegratorSettings::new(Method::Rk87)
+        };
+
+        // Bigger area for the mass is a smaller ballistic coefficient
+        let times: Vec<f64> = [200.0, 100.0, 50.0]
+            .iter()
+            .map(|&ballistic_coefficient| {
+                let model = (
+                    TwoBody::new(planet.gm),
+                    Drag::earth(Atmosphere::Us76, ballistic_coefficient),
+                );
+                lifetime(&body, &model, radius, altitude(150.0), 100.0, &settings)
+                    .unwrap()
+                    .unwrap()
+                    .time
+            })
+            .collect();
+        assert!(times[0] > times[1] && times[1] > times[2], "{:?}", times);
+        // Decay goes roughly as the ballistic coefficient
+        assert!((times[0] / times[2] - 4.0).abs() < 1.0, "{:?}", times);
+    }
+
+    #[test]
+    fn lifetime_already_down() {
+        let planet = Planet::earth();
+        let start = planet.radius + Length::km(100.0).to_au();
+        let body = Body::new(
+            Vector3::new(start, 0.0, 0.0),
+            Vector3::new(0.0, (planet.gm / start).sqrt(), 0.0),
+            planet.gm,
+        );
+        let model = (
+            TwoBody::new(planet.gm),
+            Drag::earth(Atmosphere::Exponential, 100.0),
+        );
+        let settings = IntegratorSettings::default();
+        let down = lifetime(
+            &body,
+            &model,
+            planet.radius,
+            Length::km(120.0).to_au(),
+            10.0,
+            &settings,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(down.time, 0.0);
+    }
+}
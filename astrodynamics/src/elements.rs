@@ -0,0 +1,272 @@
+/*!
+ * Classical Keplerian element sets. Body computes each element on its own
+ * from the state vectors; KeplerianElements bundles them so a published
+ * element set can be turned into a Body and a Body written back out as
+ * elements.
+ */
+use nalgebra::Vector3;
+use std::f64::consts::PI;
+
+use super::body::{three_one_three_transform, Body};
+use super::error::OrbitError;
+
+/* Eccentricity, or sine of the inclination, below which the periapsis or
+ * node isn't well defined, and how close to one an eccentricity has to be
+ * to count as parabolic */
+const SINGULAR: f64 = 1e-11;
+
+/// Classical orbital elements. Angles are in radians and the semi-major
+/// axis is in AU (negative for hyperbolic orbits), matching Body.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeplerianElements {
+    pub semi_major_axis: f64,
+    pub eccentricity: f64,
+    pub inclination: f64,
+    pub argument_of_periapsis: f64,
+    pub argument_of_ascending_node: f64,
+    pub true_anomaly: f64,
+}
+
+impl KeplerianElements {
+    /// Build an element set from angles in degrees, the way Horizons and
+    /// most published tables list them
+    pub fn from_degrees(
+        semi_major_axis: f64,
+        eccentricity: f64,
+        inclination: f64,
+        argument_of_periapsis: f64,
+        argument_of_ascending_node: f64,
+        true_anomaly: f64,
+    ) -> KeplerianElements {
+        KeplerianElements {
+            semi_major_axis,
+            eccentricity,
+            inclination: inclination.to_radians(),
+            argument_of_periapsis: argument_of_periapsis.to_radians(),
+            argument_of_ascending_node: argument_of_ascending_node.to_radians(),
+            true_anomaly: true_anomaly.to_radians(),
+        }
+    }
+
+    pub fn orbital_parameter(&self) -> f64 {
+        self.semi_major_axis * (1.0 - self.eccentricity.powi(2))
+    }
+}
+
+impl std::fmt::Display for KeplerianElements {
+    // Print the elements with angles in degrees
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "\
+             Semi-Major Axis:       {:.10e}\n\
+             Eccentricity:          {:.10e}\n\
+             Inclination:           {:.10e}\n\
+             Arg. of Periapsis:     {:.10e}\n\
+             Arg. of Asc. Node:     {:.10e}\n\
+             True Anomaly:          {:.10e}",
+            self.semi_major_axis,
+            self.eccentricity,
+            self.inclination.to_degrees(),
+            self.argument_of_periapsis.to_degrees(),
+            self.argument_of_ascending_node.to_degrees(),
+            self.true_anomaly.to_degrees()
+        )
+    }
+}
+
+impl Body {
+    /// Create a body from a set of Keplerian elements about a central body
+    /// with gravitational parameter mu. A semi-major axis can't describe a
+    /// parabola, so e = 1 is an error, as is a true anomaly past a
+    /// hyperbola's asymptote.
+    pub fn from_elements(elements: &KeplerianElements, mu: f64) -> Result<Body, OrbitError> {
+        let e = elements.eccentricity;
+        if (e - 1.0).abs() < SINGULAR {
+            return Err(OrbitError::Parabolic);
+        }
+        let t_anom = elements.true_anomaly;
+        let p = elements.orbital_parameter();
+        let radius = p / (1.0 + e * t_anom.cos());
+        if !(p > 0.0 && radius > 0.0 && radius.is_finite()) {
+            return Err(OrbitError::DegenerateState);
+        }
+
+        // State in the perifocal frame, x toward periapsis and z along h
+        let r_p = Vector3::new(radius * t_anom.cos(), radius * t_anom.sin(), 0.0);
+        let v_p = (mu / p).sqrt() * Vector3::new(-t_anom.sin(), e + t_anom.cos(), 0.0);
+
+        // three_one_three_transform goes inertial -> perifocal, so its
+        // transpose brings the state back out
+        let trans_mat = three_one_three_transform(
+            elements.argument_of_periapsis,
+            elements.inclination,
+            elements.argument_of_ascending_node,
+        )
+        .transpose();
+        Ok(Body::new(trans_mat * r_p, trans_mat * v_p, mu))
+    }
+
+    /// The body's current Keplerian elements. On a circular orbit the
+    /// argument of periapsis is zero and the true anomaly is measured from
+    /// the node; on an equatorial one the node is zero and the angles are
+    /// measured from x. Either way from_elements gets the same state back.
+    pub fn elements(&self) -> KeplerianElements {
+        let h = self.angular_momentum();
+        let h_hat = h.normalize();
+        let node = self.ascending_node();
+        let e_vec = self.eccentricity_vector();
+
+        // Angle from a direction to a vector, both in the orbit plane,
+        // going the way the body moves
+        let angle = |from: &Vector3<f64>, to: &Vector3<f64>| {
+            to.dot(&h_hat.cross(from))
+                .atan2(to.dot(from))
+                .rem_euclid(2.0 * PI)
+        };
+
+        let node_hat = if node.norm() > SINGULAR * h.norm() {
+            node.normalize()
+        } else {
+            Vector3::x()
+        };
+        let periapsis_hat = if e_vec.norm() > SINGULAR {
+            e_vec.normalize()
+        } else {
+            node_hat
+        };
+        KeplerianElements {
+            semi_major_axis: self.semi_major_axis(),
+            eccentricity: e_vec.norm(),
+            inclination: self.inclination(),
+            argument_of_periapsis: angle(&node_hat, &periapsis_hat),
+            argument_of_ascending_node: node_hat[1].atan2(node_hat[0]).rem_euclid(2.0 * PI),
+            true_anomaly: angle(&periapsis_hat, &self.position),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::SOLARGM;
+
+    /* Body -> elements -> Body, which has to land back on the same state
+     * even when some of the angles aren't well defined */
+    fn assert_round_trip(body: &Body) {
+        let back = Body::from_elements(&body.elements(), body.mu).unwrap();
+        let position_error = (back.position - body.position).norm() / body.position.norm();
+        let velocity_error = (back.velocity - body.velocity).norm() / body.velocity.norm();
+        assert!(
+            position_error < 1e-10,
+            "position off by {:e}",
+            position_error
+        );
+        assert!(
+            velocity_error < 1e-10,
+            "velocity off by {:e}",
+            velocity_error
+        );
+    }
+
+    #[test]
+    fn elliptic_elements_round_trip() {
+        let elements = KeplerianElements::from_degrees(1.5, 0.3, 12.0, 40.0, 75.0, 130.0);
+        let body = Body::from_elements(&elements, SOLARGM).unwrap();
+        let back = body.elements();
+        assert!((back.semi_major_axis - 1.5).abs() < 1e-12);
+        assert!((back.eccentricity - 0.3).abs() < 1e-12);
+        assert!((back.inclination - elements.inclination).abs() < 1e-12);
+        assert!((back.argument_of_periapsis - elements.argument_of_periapsis).abs() < 1e-10);
+        assert!(
+            (back.argument_of_ascending_node - elements.argument_of_ascending_node).abs() < 1e-12
+        );
+        assert!((back.true_anomaly - elements.true_anomaly).abs() < 1e-10);
+        assert_round_trip(&body);
+    }
+
+    #[test]
+    fn elliptic_past_apoapsis_round_trips() {
+        let elements = KeplerianElements::from_degrees(2.0, 0.6, 100.0, 300.0, 200.0, 250.0);
+        assert_round_trip(&Body::from_elements(&elements, SOLARGM).unwrap());
+    }
+
+    #[test]
+    fn circular_inclined_round_trips() {
+        let radius = 1.2;
+        let speed = (SOLARGM / radius).sqrt();
+        let inclination: f64 = 0.4;
+        let body = Body::new(
+            Vector3::new(0.0, radius * inclination.cos(), radius * inclination.sin()),
+            Vector3::new(-speed, 0.0, 0.0),
+            SOLARGM,
+        );
+        assert!(body.eccentricity() < 1e-12);
+        assert_round_trip(&body);
+    }
+
+    #[test]
+    fn equatorial_elliptic_round_trips() {
+        let elements = KeplerianElements::from_degrees(1.0, 0.2, 0.0, 0.0, 0.0, 60.0);
+        let body = Body::new(
+            Vector3::new(0.6, 0.7, 0.0),
+            Vector3::new(-0.012, 0.011, 0.0),
+            SOLARGM,
+        );
+        assert!(body.inclination().abs() < 1e-12);
+        assert_round_trip(&body);
+        assert_round_trip(&Body::from_elements(&elements, SOLARGM).unwrap());
+    }
+
+    #[test]
+    fn circular_equatorial_round_trips() {
+        let radius = 0.7;
+        let speed = (SOLARGM / radius).sqrt();
+        let body = Body::new(
+            Vector3::new(-radius * 0.6, radius * 0.8, 0.0),
+            Vector3::new(-speed * 0.8, -speed * 0.6, 0.0),
+            SOLARGM,
+        );
+        assert_round_trip(&body);
+    }
+
+    #[test]
+    fn retrograde_equatorial_round_trips() {
+        let body = Body::new(
+            Vector3::new(0.6, 0.7, 0.0),
+            Vector3::new(0.012, -0.011, 0.0),
+            SOLARGM,
+        );
+        assert!((body.inclination() - PI).abs() < 1e-12);
+        assert_round_trip(&body);
+    }
+
+    #[test]
+    fn hyperbolic_elements_round_trip() {
+        let elements = KeplerianElements::from_degrees(-3.0, 1.4, 25.0, 60.0, 110.0, -40.0);
+        let body = Body::from_elements(&elements, SOLARGM).unwrap();
+        let back = body.elements();
+        assert!((back.semi_major_axis + 3.0).abs() < 1e-10);
+        assert!((back.eccentricity - 1.4).abs() < 1e-12);
+        assert_round_trip(&body);
+    }
+
+    #[test]
+    fn parabolic_and_impossible_elements_are_errors() {
+        let parabola = KeplerianElements {
+            semi_major_axis: f64::INFINITY,
+            ..KeplerianElements::from_degrees(1.0, 1.0, 10.0, 20.0, 30.0, 40.0)
+        };
+        assert_eq!(
+            Body::from_elements(&parabola, SOLARGM).unwrap_err(),
+            OrbitError::Parabolic
+        );
+
+        // A hyperbola with e = 1.4 never reaches 140 degrees of true anomaly
+        let past_asymptote = KeplerianElements::from_degrees(-3.0, 1.4, 25.0, 60.0, 110.0, 140.0);
+        assert_eq!(
+            Body::from_elements(&past_asymptote, SOLARGM).unwrap_err(),
+            OrbitError::DegenerateState
+        );
+    }
+}
@@ -0,0 +1,307 @@
+/*!
+ * Cowell and Encke formulations for perturbed propagation. Cowell's method
+ * integrates the whole acceleration, central body included. Encke's
+ * method integrates only how far the body has drifted from a reference
+ * two-body orbit, which it gets exactly from UniversalPropagator. With
+ * light perturbations that deviation changes slowly and the integrator
+ * can take much bigger steps through it.
+ *
+ * The deviation obeys
+ *
+ *   dr'' = mu / r^3 (f(q) rho - dr) + a_p
+ *
+ * with rho the reference position, r = rho + dr the true one and a_p the
+ * perturbations. The two-body part is the difference of two nearly equal
+ * pulls, so it goes through Battin's f(q) like the third-body indirect term
+ * (Battin, section 8.3).
+ *
+ * Once the deviation grows past a small fraction of the radius, the orbit
+ * is rectified: the true state becomes the new reference orbit and the
+ * deviation starts over from zero.
+ */
+use nalgebra::{DVector, Vector3};
+use std::cell::Cell;
+
+use super::body::Body;
+use super::error::OrbitError;
+use super::force::{ForceModel, TwoBody};
+use super::integrator::{integrate, pack, unpack, IntegratorSettings, Solution};
+use super::trajectory::{State, Trajectory};
+use super::universal::UniversalPropagator;
+
+/* Deviation, as a fraction of the reference radius, that triggers
+ * rectification */
+const RECTIFICATION: f64 = 1e-2;
+
+/* Integration runs this many chunks per reference orbit, so a
+ * rectification partway through only throws out the rest of one chunk */
+const CHUNKS_PER_ORBIT: f64 = 4.0;
+
+/// How a perturbed orbit is integrated
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Formulation {
+    /// The whole acceleration
+    Cowell,
+    /// The deviation from a reference two-body orbit, with rectification
+    Encke,
+}
+
+/* A stretch of an Encke integration, from the solution's start to end,
+ * measured from a reference orbit that osculates at epoch */
+struct Arc {
+    epoch: f64,
+    reference: UniversalPropagator,
+    end: f64,
+    solution: Solution,
+}
+
+impl Arc {
+    fn state_at(&self, time: f64) -> Result<(Vector3<f64>, Vector3<f64>), OrbitError> {
+        let (deviation, rate) = unpack(
+            &self
+                .solution
+                .state_at(time)
+                .ok_or(OrbitError::DegenerateState)?,
+        );
+        let (position, velocity) = self.reference.state_at(time - self.epoch)?;
+        Ok((position + deviation, velocity + rate))
+    }
+}
+
+/* Encke integration of a body for duration days, which can be negative */
+fn encke_arcs<M: ForceModel>(
+    body: &Body,
+    perturbations: &M,
+    duration: f64,
+    settings: &IntegratorSettings,
+) -> Result<Vec<Arc>, OrbitError> {
+    let mu = body.mu;
+    let direction = duration.signum();
+    let mut arcs = Vec::new();
+    let mut time = 0.0;
+    let mut epoch = 0.0;
+    let mut osculating = body.clone();
+    let mut reference = UniversalPropagator::new(body.position, body.velocity, mu)?;
+    let mut deviation = DVector::zeros(6);
+
+    while (duration - time) * direction > 0.0 {
+        let chunk = if osculating.eccentricity() < 1.0 {
+            osculating.orbital_period() / CHUNKS_PER_ORBIT
+        } else {
+            f64::INFINITY
+        };
+        let end = if chunk < (duration - time).abs() {
+            time + direction * chunk
+        } else {
+            duration
+        };
+
+        // The derivative can't return an error, so a failed Kepler solve
+        // gets parked here and reported once the integrator is done
+        let failure = Cell::new(None);
+        let solution = integrate(
+            |t, y| {
+                let (offset, rate) = unpack(y);
+                let (rho, nu) = match reference.state_at(t - epoch) {
+                    Ok(state) => state,
+                    Err(error) => {
+                        failure.set(Some(error));
+                        return DVector::zeros(6);
+                    }
+                };
+                let position = rho + offset;
+                let q = offset.dot(&(offset + 2.0 * rho)) / rho.norm_squared();
+                let f = q * (3.0 + 3.0 * q + q.powi(2)) / (1.0 + (1.0 + q).powf(1.5));
+                let acceleration = mu / position.norm().powi(3) * (f * rho - offset)
+                    + perturbations.acceleration(t, &position, &(nu + rate));
+                pack(&rate, &acceleration)
+            },
+            time,
+            deviation,
+            end,
+            settings,
+        )?;
+        if let Some(error) = failure.get() {
+            return Err(error);
+        }
+
+        // First step where the deviation has grown too big, if any
+        let too_far = |t: f64| -> Result<bool, OrbitError> {
+            let (offset, _) = unpack(&solution.state_at(t).ok_or(OrbitError::DegenerateState)?);
+            let (rho, _) = reference.state_at(t - epoch)?;
+            Ok(offset.norm() > RECTIFICATION * rho.norm())
+        };
+        let mut cut = None;
+        for t in solution.times().skip(1) {
+            if too_far(t)? {
+                cut = Some(t);
+                break;
+            }
+        }
+
+        let stop = cut.unwrap_or(end);
+        let arc = Arc {
+            epoch,
+            reference,
+            end: stop,
+            solution,
+        };
+        if cut.is_some() {
+            let (position, velocity) = arc.state_at(stop)?;
+            osculating = Body::new(position, velocity, mu);
+            reference = UniversalPropagator::new(position, velocity, mu)?;
+            epoch = stop;
+            deviation = DVector::zeros(6);
+        } else {
+            deviation = arc
+                .solution
+                .state_at(stop)
+                .ok_or(OrbitError::DegenerateState)?;
+        }
+        arcs.push(arc);
+        time = stop;
+    }
+    Ok(arcs)
+}
+
+impl Body {
+    /// Trajectory through every epoch in times (days from now) under the
+    /// central body's gravity plus perturbations, integrated with either
+    /// formulation. Leave the central body out of perturbations; it's
+    /// added here from mu.
+    pub fn propagate_perturbed<M, I>(
+        &self,
+        perturbations: &M,
+        formulation: Formulation,
+        times: I,
+        settings: &IntegratorSettings,
+    ) -> Result<Trajectory, OrbitError>
+    where
+        M: ForceModel,
+        I: IntoIterator<Item = f64>,
+    {
+        if formulation == Formulation::Cowell {
+            let model = (
+                TwoBody::new(self.mu),
+                |t, position: &Vector3<f64>, velocity: &Vector3<f64>| {
+                    perturbations.acceleration(t, position, velocity)
+                },
+            );
+            return self.propagate_numerical(&model, times, settings);
+        }
+
+        let times: Vec<f64> = times.into_iter().collect();
+        let latest = times.iter().cloned().fold(0.0, f64::max);
+        let earliest = times.iter().cloned().fold(0.0, f64::min);
+        let forward = encke_arcs(self, perturbations, latest, settings)?;
+        let backward = encke_arcs(self, perturbations, earliest, settings)?;
+
+        let states = times
+            .iter()
+            .map(|&time| {
+                let (position, velocity) = if time == 0.0 {
+                    (self.position, self.velocity)
+                } else {
+                    // Arcs run outward from zero, so the first one that
+                    // reaches time holds it
+                    let (arcs, direction) = if time < 0.0 {
+                        (&backward, -1.0)
+                    } else {
+                        (&forward, 1.0)
+                    };
+                    arcs.iter()
+                        .find(|arc| (arc.end - time) * direction >= 0.0)
+                        .ok_or(OrbitError::DegenerateState)?
+                        .state_at(time)?
+                };
+                Ok(State {
+                    time,
+                    position,
+                    velocity,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Trajectory {
+            mu: self.mu,
+            states,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elements::KeplerianElements;
+    use crate::integrator::Method;
+    use crate::oblateness::Oblateness;
+    use crate::units::{Length, AUTOM};
+
+    /* Low Earth orbit, where J2 pulls it off its Kepler orbit fast */
+    fn leo(field: &Oblateness) -> Body {
+        let elements = KeplerianElements::from_degrees(
+            Length::km(7000.0).to_au(),
+            0.01,
+            50.0,
+            30.0,
+            40.0,
+            10.0,
+        );
+        Body::from_elements(&elements, field.gm).unwrap()
+    }
+
+    fn settings() -> IntegratorSettings {
+        IntegratorSettings {
+            tolerance: 1e-12,
+            absolute_tolerance: 1e-16,
+            ..IntegratorSettings::new(Method::Rk87)
+        }
+    }
+
+    #[test]
+    fn encke_matches_cowell() {
+        let field = Oblateness::earth();
+        let body = leo(&field);
+        let times: Vec<f64> = (-8..=16).map(|step| f64::from(step) * 0.25).collect();
+        let encke = body
+            .propagate_perturbed(&field, Formulation::Encke, times.clone(), &settings())
+            .unwrap();
+        let cowell = body
+            .propagate_numerical(&(TwoBody::new(field.gm), field), times, &settings())
+            .unwrap();
+
+        // Within a meter after four days and about 60 orbits
+        let tolerance = Length::km(1e-3).to_au();
+        for (a, b) in encke.iter().zip(&cowell) {
+            assert_eq!(a.time, b.time);
+            let error = (a.position - b.position).norm();
+            assert!(error < tolerance, "{} days: {} m", a.time, error * AUTOM);
+        }
+    }
+
+    #[test]
+    fn rectifies_under_j2() {
+        let field = Oblateness::earth();
+        let body = leo(&field);
+        let arcs = encke_arcs(&body, &field, 2.0, &settings()).unwrap();
+        let rectified = arcs.iter().filter(|arc| arc.epoch != 0.0).count();
+        assert!(rectified > 0);
+        // Each new reference orbit starts where the last arc was cut off
+        for pair in arcs.windows(2) {
+            if pair[1].epoch != pair[0].epoch {
+                assert_eq!(pair[1].epoch, pair[0].end);
+            }
+        }
+    }
+
+    #[test]
+    fn unperturbed_encke_stays_on_the_reference() {
+        let field = Oblateness::earth();
+        let body = leo(&field);
+        let none = |_: f64, _: &Vector3<f64>, _: &Vector3<f64>| Vector3::zeros();
+        let arcs = encke_arcs(&body, &none, 2.0, &settings()).unwrap();
+        assert!(arcs.iter().all(|arc| arc.epoch == 0.0));
+        let (position, _) = arcs.last().unwrap().state_at(2.0).unwrap();
+        let expected = body.position_at_time(2.0).unwrap();
+        assert!((position - expected).norm() < 1e-12 * expected.norm());
+    }
+}
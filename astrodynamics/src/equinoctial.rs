@@ -0,0 +1,289 @@
+/*!
+ * Equinoctial and modified equinoctial element sets. The classical elements
+ * fall apart for circular or equatorial orbits since the argument of
+ * periapsis and the ascending node stop being defined; these sets fold
+ * those angles into longitudes measured from the x axis, so they stay
+ * smooth all the way down to e = 0 and i = 0. The only singular orbit left
+ * is an exactly retrograde equatorial one (i = 180 degrees).
+ *
+ * Conversions go straight between the state vectors and the elements, they
+ * never pass through the classical angles.
+ */
+use nalgebra::Vector3;
+
+use super::body::Body;
+use super::error::OrbitError;
+
+const TOLERANCE: f64 = 1e-14;
+const MAX_ITERATIONS: usize = 50;
+
+/// Equinoctial elements (Broucke and Cefola), elliptic orbits only.
+///
+/// h = e sin(w + W), k = e cos(w + W), p = tan(i/2) sin(W), q = tan(i/2) cos(W)
+/// and the mean longitude is M + w + W, with w the argument of periapsis and
+/// W the argument of the ascending node. Angles are in radians, the
+/// semi-major axis is in AU.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EquinoctialElements {
+    pub semi_major_axis: f64,
+    pub h: f64,
+    pub k: f64,
+    pub p: f64,
+    pub q: f64,
+    pub mean_longitude: f64,
+}
+
+/// Modified equinoctial elements (Walker, Ireland and Owens), valid for
+/// every orbit type including parabolic.
+///
+/// f = e cos(w + W), g = e sin(w + W), h = tan(i/2) cos(W), k = tan(i/2) sin(W)
+/// and the true longitude is the true anomaly + w + W. The orbital parameter
+/// is in AU.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModifiedEquinoctialElements {
+    pub orbital_parameter: f64,
+    pub f: f64,
+    pub g: f64,
+    pub h: f64,
+    pub k: f64,
+    pub true_longitude: f64,
+}
+
+impl Body {
+    /// Create a body from modified equinoctial elements about a central body
+    /// with gravitational parameter mu
+    pub fn from_modified_equinoctial(elements: &ModifiedEquinoctialElements, mu: f64) -> Body {
+        let ModifiedEquinoctialElements {
+            orbital_parameter: p,
+            f,
+            g,
+            h,
+            k,
+            true_longitude: l,
+        } = *elements;
+        let (f_hat, g_hat) = equinoctial_frame(h, k);
+        let radius = p / (1.0 + f * l.cos() + g * l.sin());
+        let coeff = (mu / p).sqrt();
+        let position = radius * (l.cos() * f_hat + l.sin() * g_hat);
+        let velocity = coeff * (-(l.sin() + g) * f_hat + (l.cos() + f) * g_hat);
+        Body::new(position, velocity, mu)
+    }
+
+    /// The body's current modified equinoctial elements
+    pub fn modified_equinoctial(&self) -> ModifiedEquinoctialElements {
+        let ang_moment = self.angular_momentum();
+        let h_hat = ang_moment.normalize();
+        let h = -h_hat[1] / (1.0 + h_hat[2]);
+        let k = h_hat[0] / (1.0 + h_hat[2]);
+        let (f_hat, g_hat) = equinoctial_frame(h, k);
+        let e_vec = self.eccentricity_vector();
+        let posit = self.position.normalize();
+        ModifiedEquinoctialElements {
+            orbital_parameter: ang_moment.norm_squared() / self.mu,
+            f: e_vec.dot(&f_hat),
+            g: e_vec.dot(&g_hat),
+            h,
+            k,
+            true_longitude: wrap_angle(posit.dot(&g_hat).atan2(posit.dot(&f_hat))),
+        }
+    }
+
+    /// Create a body from equinoctial elements about a central body with
+    /// gravitational parameter mu
+    pub fn from_equinoctial(elements: &EquinoctialElements, mu: f64) -> Body {
+        let EquinoctialElements {
+            semi_major_axis: a,
+            h,
+            k,
+            p,
+            q,
+            mean_longitude,
+        } = *elements;
+        let (f_hat, g_hat) = equinoctial_frame(q, p);
+        let ecc_lon = eccentric_longitude(mean_longitude, k, h);
+        let beta = 1.0 / (1.0 + (1.0 - h.powi(2) - k.powi(2)).sqrt());
+        let n = (mu / a.powi(3)).sqrt();
+        let (sin_f, cos_f) = ecc_lon.sin_cos();
+        let radius = a * (1.0 - k * cos_f - h * sin_f);
+
+        // Position and velocity in the equinoctial frame
+        let x = a * ((1.0 - h.powi(2) * beta) * cos_f + h * k * beta * sin_f - k);
+        let y = a * ((1.0 - k.powi(2) * beta) * sin_f + h * k * beta * cos_f - h);
+        let x_dot =
+            (a.powi(2) * n / radius) * (h * k * beta * cos_f - (1.0 - h.powi(2) * beta) * sin_f);
+        let y_dot =
+            (a.powi(2) * n / radius) * ((1.0 - k.powi(2) * beta) * cos_f - h * k * beta * sin_f);
+
+        Body::new(x * f_hat + y * g_hat, x_dot * f_hat + y_dot * g_hat, mu)
+    }
+
+    /// The body's current equinoctial elements. They're built on the
+    /// semi-major axis and mean longitude, so open orbits are an error; use
+    /// modified_equinoctial for those.
+    pub fn equinoctial(&self) -> Result<EquinoctialElements, OrbitError> {
+        if self.eccentricity() >= 1.0 {
+            return Err(OrbitError::OpenOrbit);
+        }
+        let mee = self.modified_equinoctial();
+        let (k, h) = (mee.f, mee.g);
+        let (f_hat, g_hat) = equinoctial_frame(mee.h, mee.k);
+        let a = self.semi_major_axis();
+        let x = self.position.dot(&f_hat);
+        let y = self.position.dot(&g_hat);
+        let beta = 1.0 / (1.0 + (1.0 - h.powi(2) - k.powi(2)).sqrt());
+        let denom = a * (1.0 - h.powi(2) - k.powi(2)).sqrt();
+        let cos_f = k + ((1.0 - k.powi(2) * beta) * x - h * k * beta * y) / denom;
+        let sin_f = h + ((1.0 - h.powi(2) * beta) * y - h * k * beta * x) / denom;
+        let ecc_lon = sin_f.atan2(cos_f);
+        Ok(EquinoctialElements {
+            semi_major_axis: a,
+            h,
+            k,
+            p: mee.k,
+            q: mee.h,
+            mean_longitude: wrap_angle(ecc_lon - k * ecc_lon.sin() + h * ecc_lon.cos()),
+        })
+    }
+}
+
+/* Unit vectors of the equinoctial frame, built from tan(i/2) cos(W) and
+ * tan(i/2) sin(W). The third vector would be along the angular momentum. */
+fn equinoctial_frame(tan_cos: f64, tan_sin: f64) -> (Vector3<f64>, Vector3<f64>) {
+    let s_sq = 1.0 + tan_cos.powi(2) + tan_sin.powi(2);
+    let f_hat = Vector3::new(
+        1.0 - tan_sin.powi(2) + tan_cos.powi(2),
+        2.0 * tan_cos * tan_sin,
+        -2.0 * tan_sin,
+    ) / s_sq;
+    let g_hat = Vector3::new(
+        2.0 * tan_cos * tan_sin,
+        1.0 + tan_sin.powi(2) - tan_cos.powi(2),
+        2.0 * tan_cos,
+    ) / s_sq;
+    (f_hat, g_hat)
+}
+
+/* Solve the equinoctial form of Kepler's equation,
+ * mean_lon = F - k sin(F) + h cos(F), for the eccentric longitude F */
+fn eccentric_longitude(mean_lon: f64, k: f64, h: f64) -> f64 {
+    let kep = |f: f64| f - k * f.sin() + h * f.cos() - mean_lon;
+    let kep_d = |f: f64| 1.0 - k * f.cos() - h * f.sin();
+    let mut f = mean_lon;
+    for _ in 0..MAX_ITERATIONS {
+        let delta = kep(f) / kep_d(f);
+        f -= delta;
+        if delta.abs() < TOLERANCE {
+            break;
+        }
+    }
+    f
+}
+
+/* Keep longitudes in [0, 2 pi) like the rest of the angles in Body */
+fn wrap_angle(angle: f64) -> f64 {
+    angle.rem_euclid(2.0 * std::f64::consts::PI)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::SOLARGM;
+    use crate::elements::KeplerianElements;
+
+    fn body(elements: KeplerianElements) -> Body {
+        Body::from_elements(&elements, SOLARGM).unwrap()
+    }
+
+    fn assert_same_state(back: &Body, body: &Body) {
+        let position_error = (back.position - body.position).norm() / body.position.norm();
+        let velocity_error = (back.velocity - body.velocity).norm() / body.velocity.norm();
+        assert!(
+            position_error < 1e-12,
+            "position off by {:e}",
+            position_error
+        );
+        assert!(
+            velocity_error < 1e-12,
+            "velocity off by {:e}",
+            velocity_error
+        );
+    }
+
+    /* Elliptic orbits, including the ones the classical elements struggle
+     * with */
+    fn closed_orbits() -> Vec<Body> {
+        vec![
+            body(KeplerianElements::from_degrees(
+                1.5, 0.3, 12.0, 40.0, 75.0, 130.0,
+            )),
+            body(KeplerianElements::from_degrees(
+                2.0, 0.6, 100.0, 300.0, 200.0, 250.0,
+            )),
+            // Near-equatorial, where the node is barely defined
+            body(KeplerianElements::from_degrees(
+                1.0, 0.1, 1e-7, 30.0, 80.0, 10.0,
+            )),
+            // Circular and equatorial
+            body(KeplerianElements::from_degrees(
+                0.7, 0.0, 0.0, 0.0, 0.0, 220.0,
+            )),
+            // Retrograde, close to the singular i = 180 degrees
+            body(KeplerianElements::from_degrees(
+                1.2, 0.2, 150.0, 70.0, 20.0, 300.0,
+            )),
+            body(KeplerianElements::from_degrees(
+                1.2, 0.2, 179.0, 70.0, 20.0, 300.0,
+            )),
+        ]
+    }
+
+    #[test]
+    fn equinoctial_round_trips() {
+        for body in closed_orbits() {
+            let back = Body::from_equinoctial(&body.equinoctial().unwrap(), SOLARGM);
+            assert_same_state(&back, &body);
+        }
+    }
+
+    #[test]
+    fn modified_equinoctial_round_trips() {
+        let mut bodies = closed_orbits();
+        bodies.push(body(KeplerianElements::from_degrees(
+            -3.0, 1.4, 25.0, 60.0, 110.0, -40.0,
+        )));
+        bodies.push(body(KeplerianElements::from_degrees(
+            -0.5, 3.0, 170.0, 10.0, 200.0, 50.0,
+        )));
+        for body in bodies {
+            let back = Body::from_modified_equinoctial(&body.modified_equinoctial(), SOLARGM);
+            assert_same_state(&back, &body);
+        }
+    }
+
+    #[test]
+    fn modified_equinoctial_handles_parabolas() {
+        // e = 1, with the periapsis along the g axis
+        let elements = ModifiedEquinoctialElements {
+            orbital_parameter: 2.0,
+            f: 0.0,
+            g: 1.0,
+            h: 0.1,
+            k: -0.2,
+            true_longitude: 2.5,
+        };
+        let parabola = Body::from_modified_equinoctial(&elements, SOLARGM);
+        assert!((parabola.eccentricity() - 1.0).abs() < 1e-12);
+        let back = parabola.modified_equinoctial();
+        assert!((back.orbital_parameter - 2.0).abs() < 1e-12);
+        assert!((back.g - 1.0).abs() < 1e-12);
+        assert!((back.true_longitude - 2.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn equinoctial_rejects_open_orbits() {
+        let hyperbola = body(KeplerianElements::from_degrees(
+            -3.0, 1.4, 25.0, 60.0, 110.0, -40.0,
+        ));
+        assert_eq!(hyperbola.equinoctial().unwrap_err(), OrbitError::OpenOrbit);
+    }
+}
@@ -0,0 +1,64 @@
+/*!
+ * Errors for orbits that the library can't do anything sensible with.
+ * These used to show up as an "Invalid Orbit" message on stderr and a NaN
+ * in the output; now they're returned so the caller can decide what to do.
+ */
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OrbitError {
+    /// Kepler's equation has no eccentric anomaly on a parabolic orbit, and
+    /// a semi-major axis can't describe one
+    Parabolic,
+    /// An iterative solver hit its iteration cap before converging
+    NoConvergence { iterations: usize, residual: f64 },
+    /// The state can't describe an orbit, e.g. a zero position vector,
+    /// a non-positive mu or values that aren't finite
+    DegenerateState,
+    /// Lambert's problem with positions on the same line through the
+    /// central body, which doesn't pin down a transfer plane
+    Collinear,
+    /// No orbit fits the boundary conditions, e.g. a multi-revolution
+    /// transfer asked to go faster than its minimum time of flight
+    NoSolution,
+    /// Needs a closed orbit, circular or elliptic
+    OpenOrbit,
+    /// The two orbits aren't in the same plane, or go opposite ways in it
+    NotCoplanar,
+    /// The trajectory runs into the surface of the body it's passing
+    Impact,
+    /// States that have to be about the same central body have different
+    /// gravitational parameters
+    CentralBodyMismatch,
+}
+
+impl fmt::Display for OrbitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OrbitError::Parabolic => {
+                write!(f, "cannot use Kepler's equation with a parabolic orbit")
+            }
+            OrbitError::NoConvergence {
+                iterations,
+                residual,
+            } => write!(
+                f,
+                "solver did not converge after {} iterations (residual {:e})",
+                iterations, residual
+            ),
+            OrbitError::DegenerateState => write!(f, "state vectors do not describe an orbit"),
+            OrbitError::Collinear => {
+                write!(f, "positions are collinear, transfer plane is undefined")
+            }
+            OrbitError::NoSolution => write!(f, "no orbit satisfies the boundary conditions"),
+            OrbitError::OpenOrbit => write!(f, "orbit is not closed"),
+            OrbitError::NotCoplanar => write!(f, "orbits are not coplanar"),
+            OrbitError::Impact => write!(f, "trajectory impacts the surface"),
+            OrbitError::CentralBodyMismatch => {
+                write!(f, "states are not about the same central body")
+            }
+        }
+    }
+}
+
+impl std::error::Error for OrbitError {}
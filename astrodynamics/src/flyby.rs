@@ -0,0 +1,253 @@
+/*!
+ * Unpowered gravity-assist flybys, patched conics style. The flyby itself
+ * is treated as instantaneous: the spacecraft's velocity relative to the
+ * planet (v-infinity) gets turned by the hyperbola without changing size,
+ * and the heliocentric velocity afterwards is the planet's velocity plus
+ * the turned v-infinity.
+ *
+ * The B-plane is set up the usual way: S along the incoming v-infinity,
+ * T = S x k normalized with k the reference z axis, and R = S x T. The
+ * B vector points from the planet to where the incoming asymptote crosses
+ * that plane, and its angle from T picks which side the spacecraft goes
+ * past on, and so which way it gets turned.
+ */
+use nalgebra::Vector3;
+
+use super::body::Body;
+use super::error::OrbitError;
+use super::planet::Planet;
+
+/// B-plane frame and the B vector in it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BPlane {
+    pub s_hat: Vector3<f64>,
+    pub t_hat: Vector3<f64>,
+    pub r_hat: Vector3<f64>,
+    /// Inertial B vector, AU
+    pub b_vector: Vector3<f64>,
+    pub b_t: f64,
+    pub b_r: f64,
+}
+
+impl BPlane {
+    /// Frame for an incoming v-infinity. Falls back to the x axis for the
+    /// reference direction when the approach is along z.
+    pub fn new(v_inf_in: Vector3<f64>, b_vector: Vector3<f64>) -> BPlane {
+        let (s_hat, t_hat, r_hat) = b_plane_axes(v_inf_in);
+        BPlane {
+            s_hat,
+            t_hat,
+            r_hat,
+            b_vector,
+            b_t: b_vector.dot(&t_hat),
+            b_r: b_vector.dot(&r_hat),
+        }
+    }
+
+    /// Magnitude of B, the impact parameter, AU
+    pub fn b_magnitude(&self) -> f64 {
+        self.b_vector.norm()
+    }
+
+    /// Angle of B measured from T toward R, radians
+    pub fn angle(&self) -> f64 {
+        self.b_r.atan2(self.b_t)
+    }
+}
+
+/// Everything about a flyby. Vectors are inertial and in AU/day, distances
+/// in AU, angles in radians.
+#[derive(Debug, Clone)]
+pub struct Flyby {
+    pub v_inf_in: Vector3<f64>,
+    pub v_inf_out: Vector3<f64>,
+    pub turn_angle: f64,
+    pub periapsis_radius: f64,
+    /// Eccentricity of the planet-centered hyperbola
+    pub eccentricity: f64,
+    pub b_plane: BPlane,
+    /// Heliocentric state right after the flyby, at the planet's position
+    pub heliocentric: Body,
+}
+
+impl Flyby {
+    /// Change in heliocentric velocity the flyby gave for free
+    pub fn delta_v(&self) -> Vector3<f64> {
+        self.v_inf_out - self.v_inf_in
+    }
+
+    /// The planet-centered hyperbola at periapsis, in the planet's frame
+    pub fn hyperbola(&self, planet: &Planet) -> Body {
+        let h_hat = self.b_plane.b_vector.cross(&self.b_plane.s_hat).normalize();
+        // Periapsis sits halfway between the asymptotes, on the inside of
+        // the turn
+        let peri_hat = (self.v_inf_in.normalize() - self.v_inf_out.normalize()).normalize();
+        let speed = (self.v_inf_in.norm_squared() + 2.0 * planet.gm / self.periapsis_radius).sqrt();
+        Body::new(
+            self.periapsis_radius * peri_hat,
+            speed * h_hat.cross(&peri_hat),
+            planet.gm,
+        )
+    }
+}
+
+/// Flyby of a planet whose heliocentric state is planet_state, by a
+/// spacecraft arriving with heliocentric velocity velocity. altitude is
+/// the periapsis altitude above the planet's radius in AU, and b_angle is
+/// the B vector's angle from T toward R in radians.
+pub fn flyby(
+    velocity: Vector3<f64>,
+    planet_state: &Body,
+    planet: &Planet,
+    altitude: f64,
+    b_angle: f64,
+) -> Result<Flyby, OrbitError> {
+    let v_inf_in = velocity - planet_state.velocity;
+    let v_inf = v_inf_in.norm();
+    if v_inf == 0.0 || !v_inf.is_finite() || planet.gm <= 0.0 {
+        return Err(OrbitError::DegenerateState);
+    }
+    if altitude < 0.0 {
+        return Err(OrbitError::NoSolution);
+    }
+
+    let periapsis_radius = planet.radius + altitude;
+    let eccentricity = 1.0 + periapsis_radius * v_inf.powi(2) / planet.gm;
+    let turn_angle = 2.0 * (1.0 / eccentricity).asin();
+    let b_magnitude =
+        periapsis_radius * (1.0 + 2.0 * planet.gm / (periapsis_radius * v_inf.powi(2))).sqrt();
+
+    let (s_hat, t_hat, r_hat) = b_plane_axes(v_inf_in);
+    let b_hat = b_angle.cos() * t_hat + b_angle.sin() * r_hat;
+
+    // The hyperbola bends the path back toward the planet, so v-infinity
+    // turns from S toward -B
+    let v_inf_out = v_inf * (turn_angle.cos() * s_hat - turn_angle.sin() * b_hat);
+
+    Ok(Flyby {
+        v_inf_in,
+        v_inf_out,
+        turn_angle,
+        periapsis_radius,
+        eccentricity,
+        b_plane: BPlane::new(v_inf_in, b_magnitude * b_hat),
+        heliocentric: Body::new(
+            planet_state.position,
+            planet_state.velocity + v_inf_out,
+            planet_state.mu,
+        ),
+    })
+}
+
+/// Periapsis radius (AU) that turns v_inf_in into the direction of
+/// v_inf_out without a burn, for a planet with gravitational parameter gm
+pub fn required_periapsis(
+    v_inf_in: Vector3<f64>,
+    v_inf_out: Vector3<f64>,
+    gm: f64,
+) -> Result<f64, OrbitError> {
+    let turn_angle = v_inf_in
+        .cross(&v_inf_out)
+        .norm()
+        .atan2(v_inf_in.dot(&v_inf_out));
+    if turn_angle <= 0.0 {
+        return Err(OrbitError::NoSolution);
+    }
+    let eccentricity = 1.0 / (turn_angle / 2.0).sin();
+    Ok((eccentricity - 1.0) * gm / v_inf_in.norm_squared())
+}
+
+/* S, T and R unit vectors for an incoming v-infinity */
+fn b_plane_axes(v_inf_in: Vector3<f64>) -> (Vector3<f64>, Vector3<f64>, Vector3<f64>) {
+    let s_hat = v_inf_in.normalize();
+    let mut t = s_hat.cross(&Vector3::z());
+    if t.norm() < 1e-12 {
+        t = s_hat.cross(&Vector3::x());
+    }
+    let t_hat = t.normalize();
+    (s_hat, t_hat, s_hat.cross(&t_hat))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::SOLARGM;
+    use crate::units::{Length, Speed};
+
+    /* Earth on a circular orbit at 1 AU, and a spacecraft coming in at
+     * 6 km/s relative to it */
+    fn approach() -> (Body, Planet, Vector3<f64>) {
+        let speed = (SOLARGM / 1.0).sqrt();
+        let earth = Body::new(Vector3::x(), Vector3::new(0.0, speed, 0.0), SOLARGM);
+        let v_inf = Speed::km_per_s(6.0).to_au_per_day();
+        let v_inf_in = v_inf * Vector3::new(0.6, -0.7, 0.2).normalize();
+        (earth, Planet::earth(), v_inf_in)
+    }
+
+    #[test]
+    fn turn_angle_and_speed() {
+        let (earth, planet, v_inf_in) = approach();
+        let altitude = Length::km(500.0).to_au();
+        for &b_angle in &[0.0, 1.0, 3.0] {
+            let flyby = flyby(
+                earth.velocity + v_inf_in,
+                &earth,
+                &planet,
+                altitude,
+                b_angle,
+            )
+            .unwrap();
+            let v_inf = v_inf_in.norm();
+            assert!((flyby.v_inf_out.norm() - v_inf).abs() < 1e-15);
+
+            let periapsis = planet.radius + altitude;
+            let eccentricity = 1.0 + periapsis * v_inf.powi(2) / planet.gm;
+            assert!((flyby.eccentricity - eccentricity).abs() < 1e-12);
+            assert!((flyby.turn_angle - 2.0 * (1.0 / eccentricity).asin()).abs() < 1e-12);
+            let turned = v_inf_in.angle(&flyby.v_inf_out);
+            assert!((turned - flyby.turn_angle).abs() < 1e-9);
+
+            // The impact parameter goes with the same hyperbola
+            let b = planet.gm / v_inf.powi(2) * (eccentricity.powi(2) - 1.0).sqrt();
+            assert!((flyby.b_plane.b_magnitude() - b).abs() < 1e-9 * b);
+            assert!((flyby.b_plane.angle() - b_angle).abs() < 1e-12);
+
+            let hyperbola = flyby.hyperbola(&planet);
+            assert!((hyperbola.eccentricity() - eccentricity).abs() < 1e-9);
+            assert!((hyperbola.position.norm() - periapsis).abs() < 1e-15);
+
+            assert_eq!(flyby.heliocentric.position, earth.position);
+            assert!(
+                (flyby.heliocentric.velocity - earth.velocity - flyby.v_inf_out).norm() < 1e-16
+            );
+        }
+    }
+
+    #[test]
+    fn required_periapsis_inverts_flyby() {
+        let (earth, planet, v_inf_in) = approach();
+        for &altitude_km in &[200.0, 5000.0, 80000.0] {
+            let altitude = Length::km(altitude_km).to_au();
+            let flyby = flyby(earth.velocity + v_inf_in, &earth, &planet, altitude, 0.7).unwrap();
+            let radius = required_periapsis(flyby.v_inf_in, flyby.v_inf_out, planet.gm).unwrap();
+            assert!((radius - flyby.periapsis_radius).abs() < 1e-9 * radius);
+        }
+        assert_eq!(
+            required_periapsis(v_inf_in, v_inf_in, planet.gm).unwrap_err(),
+            OrbitError::NoSolution
+        );
+    }
+
+    #[test]
+    fn bad_flybys_are_errors() {
+        let (earth, planet, v_inf_in) = approach();
+        assert_eq!(
+            flyby(earth.velocity, &earth, &planet, 0.0, 0.0).unwrap_err(),
+            OrbitError::DegenerateState
+        );
+        assert_eq!(
+            flyby(earth.velocity + v_inf_in, &earth, &planet, -1e-6, 0.0).unwrap_err(),
+            OrbitError::NoSolution
+        );
+    }
+}
@@ -0,0 +1,154 @@
+/*!
+ * Force models for numerical propagation. Anything that can give the
+ * acceleration on a body from its time, position and velocity is a
+ * ForceModel, closures included, and models add up by putting them in a
+ * tuple or a Vec. Accelerations are in AU/day^2 like the rest of the
+ * library. The variational equations also need the partial derivatives of
+ * the acceleration, which come from central differences unless a model
+ * supplies them itself.
+ */
+use nalgebra::{Matrix3, Vector3};
+
+/* Central difference steps are this fraction of the position or velocity,
+ * about the cube root of machine epsilon */
+const DIFFERENCE_STEP: f64 = 6e-6;
+
+/// Acceleration on a body at a time (days from the start of propagation),
+/// position (AU) and velocity (AU/day)
+pub trait ForceModel {
+    fn acceleration(
+        &self,
+        time: f64,
+        position: &Vector3<f64>,
+        velocity: &Vector3<f64>,
+    ) -> Vector3<f64>;
+
+    /// Partial derivatives of the acceleration with respect to position and
+    /// velocity, for the variational equations. Worked out by central
+    /// differences unless a model knows them exactly.
+    fn partials(
+        &self,
+        time: f64,
+        position: &Vector3<f64>,
+        velocity: &Vector3<f64>,
+    ) -> (Matrix3<f64>, Matrix3<f64>) {
+        let mut by_position = Matrix3::zeros();
+        let mut by_velocity = Matrix3::zeros();
+        let position_step = DIFFERENCE_STEP * position.norm().max(f64::EPSILON);
+        let velocity_step = DIFFERENCE_STEP * velocity.norm().max(f64::EPSILON);
+        for i in 0..3 {
+            let mut nudge = Vector3::zeros();
+            nudge[i] = position_step;
+            let change = self.acceleration(time, &(position + nudge), velocity)
+                - self.acceleration(time, &(position - nudge), velocity);
+            by_position.set_column(i, &(change / (2.0 * position_step)));
+
+            let mut nudge = Vector3::zeros();
+            nudge[i] = velocity_step;
+            let change = self.acceleration(time, position, &(velocity + nudge))
+                - self.acceleration(time, position, &(velocity - nudge));
+            by_velocity.set_column(i, &(change / (2.0 * velocity_step)));
+        }
+        (by_position, by_velocity)
+    }
+}
+
+impl<F> ForceModel for F
+where
+    F: Fn(f64, &Vector3<f64>, &Vector3<f64>) -> Vector3<f64>,
+{
+    fn acceleration(
+        &self,
+        time: f64,
+        position: &Vector3<f64>,
+        velocity: &Vector3<f64>,
+    ) -> Vector3<f64> {
+        self(time, position, velocity)
+    }
+}
+
+impl<A: ForceModel, B: ForceModel> ForceModel for (A, B) {
+    fn acceleration(
+        &self,
+        time: f64,
+        position: &Vector3<f64>,
+        velocity: &Vector3<f64>,
+    ) -> Vector3<f64> {
+        self.0.acceleration(time, position, velocity)
+            + self.1.acceleration(time, position, velocity)
+    }
+
+    fn partials(
+        &self,
+        time: f64,
+        position: &Vector3<f64>,
+        velocity: &Vector3<f64>,
+    ) -> (Matrix3<f64>, Matrix3<f64>) {
+        let (first_position, first_velocity) = self.0.partials(time, position, velocity);
+        let (second_position, second_velocity) = self.1.partials(time, position, velocity);
+        (
+            first_position + second_position,
+            first_velocity + second_velocity,
+        )
+    }
+}
+
+impl ForceModel for Vec<Box<dyn ForceModel>> {
+    fn acceleration(
+        &self,
+        time: f64,
+        position: &Vector3<f64>,
+        velocity: &Vector3<f64>,
+    ) -> Vector3<f64> {
+        self.iter()
+            .map(|model| model.acceleration(time, position, velocity))
+            .fold(Vector3::zeros(), |total, acceleration| total + acceleration)
+    }
+
+    fn partials(
+        &self,
+        time: f64,
+        position: &Vector3<f64>,
+        velocity: &Vector3<f64>,
+    ) -> (Matrix3<f64>, Matrix3<f64>) {
+        self.iter()
+            .map(|model| model.partials(time, position, velocity))
+            .fold(
+                (Matrix3::zeros(), Matrix3::zeros()),
+                |(total_position, total_velocity), (by_position, by_velocity)| {
+                    (total_position + by_position, total_velocity + by_velocity)
+                },
+            )
+    }
+}
+
+/// Point-mass gravity of the central body, -mu r / |r|^3
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TwoBody {
+    pub mu: f64,
+}
+
+impl TwoBody {
+    pub fn new(mu: f64) -> TwoBody {
+        TwoBody { mu }
+    }
+}
+
+impl ForceModel for TwoBody {
+    fn acceleration(&self, _: f64, position: &Vector3<f64>, _: &Vector3<f64>) -> Vector3<f64> {
+        -self.mu * position / position.norm().powi(3)
+    }
+
+    /// The gravity gradient, mu (3 r r^T / |r|^2 - I) / |r|^3
+    fn partials(
+        &self,
+        _: f64,
+        position: &Vector3<f64>,
+        _: &Vector3<f64>,
+    ) -> (Matrix3<f64>, Matrix3<f64>) {
+        let r = position.norm();
+        let gradient = self.mu / r.powi(3)
+            * (3.0 * position * position.transpose() / r.powi(2) - Matrix3::identity());
+        (gradient, Matrix3::zeros())
+    }
+}
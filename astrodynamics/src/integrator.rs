@@ -0,0 +1,890 @@
+/*!
+ * Adaptive Runge-Kutta integration, for propagating states that the
+ * analytic two-body solutions can't handle once perturbations are added.
+ *
+ * The integrator works on a plain state vector so the same code can carry
+ * a single body, a whole N-body system or a state with its variational
+ * equations tacked on. Body::integrate and Body::propagate_numerical pack
+ * the position and velocity into one and run it under a ForceModel.
+ *
+ * Three methods are available:
+ *   - classical RK4, with the step error estimated by step doubling
+ *   - Dormand-Prince 5(4), the usual general purpose choice
+ *   - RK8(7), Fehlberg's 13 stage pair carried along with the eighth order
+ *     solution, which takes far bigger steps at tight tolerances
+ *
+ * Every accepted step is kept for dense output, so the solution can be
+ * evaluated at any time in between without stopping the integrator on it.
+ * DP5(4) uses Dormand and Prince's fourth order continuous extension. The
+ * other two have none, so they also keep the state and derivative halfway
+ * through the step and fit a quintic through all three points; RK4 gets
+ * the midpoint for free from step doubling, RK8(7) pays for an extra half
+ * step on every accepted step.
+ */
+use nalgebra::{DVector, Vector3};
+use std::cell::Cell;
+use std::cmp::Ordering;
+
+use super::body::Body;
+use super::error::OrbitError;
+use super::force::ForceModel;
+use super::trajectory::{State, Trajectory};
+
+const TOLERANCE: f64 = 1e-10;
+const ABSOLUTE_TOLERANCE: f64 = 1e-13;
+const MAX_STEPS: usize = 1_000_000;
+
+/* Step size controller: safety factor and the most a step can shrink or
+ * grow at once */
+const SAFETY: f64 = 0.9;
+const MIN_FACTOR: f64 = 0.2;
+const MAX_FACTOR: f64 = 5.0;
+
+/* A step smaller than this relative to the time can't make any progress */
+const MIN_STEP: f64 = 1e-14;
+
+/// Runge-Kutta method used by the integrator
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Method {
+    Rk4,
+    DormandPrince54,
+    Rk87,
+}
+
+/// Tolerances and step limits for the integrator. The error of each step
+/// is kept under absolute_tolerance + tolerance * |state| for every
+/// component of the state, on average.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IntegratorSettings {
+    pub method: Method,
+    pub tolerance: f64,
+    pub absolute_tolerance: f64,
+    /// First step to try, days. Picked from the state if None.
+    pub initial_step: Option<f64>,
+    /// Largest step allowed, days
+    pub max_step: f64,
+    /// Steps tried, accepted or not, before giving up
+    pub max_steps: usize,
+}
+
+impl Default for IntegratorSettings {
+    fn default() -> IntegratorSettings {
+        IntegratorSettings {
+            method: Method::DormandPrince54,
+            tolerance: TOLERANCE,
+            absolute_tolerance: ABSOLUTE_TOLERANCE,
+            initial_step: None,
+            max_step: f64::INFINITY,
+            max_steps: MAX_STEPS,
+        }
+    }
+}
+
+impl IntegratorSettings {
+    pub fn new(method: Method) -> IntegratorSettings {
+        IntegratorSettings {
+            method,
+            ..IntegratorSettings::default()
+        }
+    }
+}
+
+/* Butcher tableau of an embedded pair. b gives the solution that gets
+ * kept and e = b - b_hat the error estimate, whose order is order. fsal
+ * means the last stage is the derivative at the end of the step. */
+struct Tableau {
+    c: &'static [f64],
+    a: &'static [&'static [f64]],
+    b: &'static [f64],
+    e: &'static [f64],
+    order: f64,
+    fsal: bool,
+}
+
+const RK4: Tableau = Tableau {
+    c: &[0.0, 0.5, 0.5, 1.0],
+    a: &[&[0.5], &[0.0, 0.5], &[0.0, 0.0, 1.0]],
+    b: &[1.0 / 6.0, 1.0 / 3.0, 1.0 / 3.0, 1.0 / 6.0],
+    e: &[],
+    order: 4.0,
+    fsal: false,
+};
+
+const DORMAND_PRINCE: Tableau = Tableau {
+    c: &[0.0, 1.0 / 5.0, 3.0 / 10.0, 4.0 / 5.0, 8.0 / 9.0, 1.0, 1.0],
+    a: &[
+        &[1.0 / 5.0],
+        &[3.0 / 40.0, 9.0 / 40.0],
+        &[44.0 / 45.0, -56.0 / 15.0, 32.0 / 9.0],
+        &[
+            19372.0 / 6561.0,
+            -25360.0 / 2187.0,
+            64448.0 / 6561.0,
+            -212.0 / 729.0,
+        ],
+        &[
+            9017.0 / 3168.0,
+            -355.0 / 33.0,
+            46732.0 / 5247.0,
+            49.0 / 176.0,
+            -5103.0 / 18656.0,
+        ],
+        &[
+            35.0 / 384.0,
+            0.0,
+            500.0 / 1113.0,
+            125.0 / 192.0,
+            -2187.0 / 6784.0,
+            11.0 / 84.0,
+        ],
+    ],
+    b: &[
+        35.0 / 384.0,
+        0.0,
+        500.0 / 1113.0,
+        125.0 / 192.0,
+        -2187.0 / 6784.0,
+        11.0 / 84.0,
+        0.0,
+    ],
+    e: &[
+        71.0 / 57600.0,
+        0.0,
+        -71.0 / 16695.0,
+        71.0 / 1920.0,
+        -17253.0 / 339200.0,
+        22.0 / 525.0,
+        -1.0 / 40.0,
+    ],
+    order: 4.0,
+    fsal: true,
+};
+
+/* Coefficients of the DP5(4) continuous extension (Hairer, Norsett and
+ * Wanner's dopri5) */
+const DENSE: [f64; 7] = [
+    -12715105075.0 / 11282082432.0,
+    0.0,
+    87487479700.0 / 32700410799.0,
+    -10690763975.0 / 1880347072.0,
+    701980252875.0 / 199316789632.0,
+    -1453857185.0 / 822651844.0,
+    69997945.0 / 29380423.0,
+];
+
+const FEHLBERG: Tableau = Tableau {
+    c: &[
+        0.0,
+        2.0 / 27.0,
+        1.0 / 9.0,
+        1.0 / 6.0,
+        5.0 / 12.0,
+        1.0 / 2.0,
+        5.0 / 6.0,
+        1.0 / 6.0,
+        2.0 / 3.0,
+        1.0 / 3.0,
+        1.0,
+        0.0,
+        1.0,
+    ],
+    a: &[
+        &[2.0 / 27.0],
+        &[1.0 / 36.0, 1.0 / 12.0],
+        &[1.0 / 24.0, 0.0, 1.0 / 8.0],
+        &[5.0 / 12.0, 0.0, -25.0 / 16.0, 25.0 / 16.0],
+        &[1.0 / 20.0, 0.0, 0.0, 1.0 / 4.0, 1.0 / 5.0],
+        &[
+            -25.0 / 108.0,
+            0.0,
+            0.0,
+            125.0 / 108.0,
+            -65.0 / 27.0,
+            125.0 / 54.0,
+        ],
+        &[
+            31.0 / 300.0,
+            0.0,
+            0.0,
+            0.0,
+            61.0 / 225.0,
+            -2.0 / 9.0,
+            13.0 / 900.0,
+        ],
+        &[
+            2.0,
+            0.0,
+            0.0,
+            -53.0 / 6.0,
+            704.0 / 45.0,
+            -107.0 / 9.0,
+            67.0 / 90.0,
+            3.0,
+        ],
+        &[
+            -91.0 / 108.0,
+            0.0,
+            0.0,
+            23.0 / 108.0,
+            -976.0 / 135.0,
+            311.0 / 54.0,
+            -19.0 / 60.0,
+            17.0 / 6.0,
+            -1.0 / 12.0,
+        ],
+        &[
+            2383.0 / 4100.0,
+            0.0,
+            0.0,
+            -341.0 / 164.0,
+            4496.0 / 1025.0,
+            -301.0 / 82.0,
+            2133.0 / 4100.0,
+            45.0 / 82.0,
+            45.0 / 164.0,
+            18.0 / 41.0,
+        ],
+        &[
+            3.0 / 205.0,
+            0.0,
+            0.0,
+            0.0,
+            0.0,
+            -6.0 / 41.0,
+            -3.0 / 205.0,
+            -3.0 / 41.0,
+            3.0 / 41.0,
+            6.0 / 41.0,
+            0.0,
+        ],
+        &[
+            -1777.0 / 4100.0,
+            0.0,
+            0.0,
+            -341.0 / 164.0,
+            4496.0 / 1025.0,
+            -289.0 / 82.0,
+            2193.0 / 4100.0,
+            51.0 / 82.0,
+            33.0 / 164.0,
+            12.0 / 41.0,
+            0.0,
+            1.0,
+        ],
+    ],
+    b: &[
+        0.0,
+        0.0,
+        0.0,
+        0.0,
+        0.0,
+        34.0 / 105.0,
+        9.0 / 35.0,
+        9.0 / 35.0,
+        9.0 / 280.0,
+        9.0 / 280.0,
+        0.0,
+        41.0 / 840.0,
+        41.0 / 840.0,
+    ],
+    e: &[
+        -41.0 / 840.0,
+        0.0,
+        0.0,
+        0.0,
+        0.0,
+        0.0,
+        0.0,
+        0.0,
+        0.0,
+        0.0,
+        -41.0 / 840.0,
+        41.0 / 840.0,
+        41.0 / 840.0,
+    ],
+    order: 7.0,
+    fsal: false,
+};
+
+/* One accepted step, with what dense output needs to fill it in */
+#[derive(Debug, Clone)]
+struct Step {
+    time: f64,
+    step: f64,
+    start: DVector<f64>,
+    end: DVector<f64>,
+    start_rate: DVector<f64>,
+    end_rate: DVector<f64>,
+    extension: Extension,
+}
+
+/* What a step adds to the cubic Hermite fit through its ends */
+#[derive(Debug, Clone)]
+enum Extension {
+    /* Nothing, just the cubic */
+    Cubic,
+    /* The last term of the DP5(4) continuous extension */
+    DormandPrince(DVector<f64>),
+    /* Coefficients of theta^2 (1 - theta)^2 (a + b (theta - 1/2)), which
+     * has no value or slope at either end and takes the fit through the
+     * midpoint state and derivative */
+    Midpoint(DVector<f64>, DVector<f64>),
+}
+
+impl Step {
+    fn interpolate(&self, time: f64) -> DVector<f64> {
+        let theta = (time - self.time) / self.step;
+        let theta_1 = 1.0 - theta;
+        let change = &self.end - &self.start;
+        let slope_0 = &self.start_rate * self.step - &change;
+        let slope_1 = &change - &self.end_rate * self.step - &slope_0;
+        match &self.extension {
+            Extension::Cubic => {
+                &self.start + (change + (slope_0 + slope_1 * theta) * theta_1) * theta
+            }
+            Extension::DormandPrince(dense) => {
+                let inner = slope_1 + dense * theta_1;
+                &self.start + (change + (slope_0 + inner * theta) * theta_1) * theta
+            }
+            Extension::Midpoint(a, b) => {
+                let cubic = &self.start + (change + (slope_0 + slope_1 * theta) * theta_1) * theta;
+                cubic + (a + b * (theta - 0.5)) * (theta * theta_1).powi(2)
+            }
+        }
+    }
+
+    /* Midpoint extension for a step with the state and derivative halfway */
+    fn midpoint(&self, state: &DVector<f64>, rate: &DVector<f64>) -> Extension {
+        // Value and slope (per unit theta) of the cubic at theta = 1/2
+        let value = (&self.start + &self.end) * 0.5
+            + (&self.start_rate - &self.end_rate) * (self.step / 8.0);
+        let slope = (&self.end - &self.start) * 1.5
+            - (&self.start_rate + &self.end_rate) * (self.step / 4.0);
+        Extension::Midpoint((state - value) * 16.0, (rate * self.step - slope) * 16.0)
+    }
+}
+
+/// Integrated solution of a state from start to end (days). The state can
+/// be looked up anywhere in between with state_at.
+#[derive(Debug, Clone)]
+pub struct Solution {
+    pub start: f64,
+    pub end: f64,
+    /// Number of times the derivative was evaluated
+    pub evaluations: usize,
+    /// Number of steps that were thrown out for being too big
+    pub rejected: usize,
+    initial: DVector<f64>,
+    steps: Vec<Step>,
+}
+
+impl Solution {
+    /// Number of accepted steps
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Times at the ends of every accepted step, starting with start
+    pub fn times(&self) -> impl Iterator<Item = f64> + '_ {
+        std::iter::once(self.start).chain(self.steps.iter().map(|step| step.time + step.step))
+    }
+
+    /// State at the end of the integration
+    pub fn final_state(&self) -> &DVector<f64> {
+        self.steps.last().map_or(&self.initial, |step| &step.end)
+    }
+
+    /// State at any time between start and end, None outside of that
+    pub fn state_at(&self, time: f64) -> Option<DVector<f64>> {
+        let direction = (self.end - self.start).signum();
+        if !((time - self.start) * direction >= 0.0 && (self.end - time) * direction >= 0.0) {
+            return None;
+        }
+        if time == self.start {
+            return Some(self.initial.clone());
+        }
+        // Step start times only go one way, so the last step starting at
+        // or before time holds it
+        let index = match self.steps.binary_search_by(|step| {
+            ((step.time - time) * direction)
+                .partial_cmp(&0.0)
+                .unwrap_or(Ordering::Less)
+        }) {
+            Ok(index) => index,
+            Err(0) => return None,
+            Err(index) => index - 1,
+        };
+        Some(self.steps[index].interpolate(time))
+    }
+}
+
+/* The outcome of trying one step: the new state, the scaled error norm
+ * (under one to accept), the stages and, if the step worked it out along
+ * the way, the state and derivative halfway through */
+struct Attempt {
+    state: DVector<f64>,
+    error: f64,
+    stages: Vec<DVector<f64>>,
+    midpoint: Option<(DVector<f64>, DVector<f64>)>,
+}
+
+/// Integrate d(state)/dt = derivative(time, state) from start to end, which
+/// can be before start to go backward
+pub fn integrate<F>(
+    derivative: F,
+    start: f64,
+    state: DVector<f64>,
+    end: f64,
+    settings: &IntegratorSettings,
+) -> Result<Solution, OrbitError>
+where
+    F: Fn(f64, &DVector<f64>) -> DVector<f64>,
+{
+    if !start.is_finite() || !end.is_finite() || state.iter().any(|value| !value.is_finite()) {
+        return Err(OrbitError::DegenerateState);
+    }
+    let evaluations = Cell::new(0);
+    let rate_of = |time: f64, state: &DVector<f64>| {
+        evaluations.set(evaluations.get() + 1);
+        derivative(time, state)
+    };
+
+    let mut solution = Solution {
+        start,
+        end,
+        evaluations: 0,
+        rejected: 0,
+        initial: state.clone(),
+        steps: Vec::new(),
+    };
+    if end == start {
+        return Ok(solution);
+    }
+
+    let direction = (end - start).signum();
+    let mut time = start;
+    let mut current = state;
+    let mut rate = rate_of(time, &current);
+    let mut step = settings
+        .initial_step
+        .unwrap_or_else(|| initial_step(&current, &rate, settings))
+        .abs();
+    let mut error = 0.0;
+
+    while (end - time) * direction > 0.0 {
+        if solution.steps.len() + solution.rejected >= settings.max_steps {
+            return Err(OrbitError::NoConvergence {
+                iterations: settings.max_steps,
+                residual: error,
+            });
+        }
+        let last = step.min(settings.max_step) >= (end - time).abs();
+        let h = direction * step.min(settings.max_step).min((end - time).abs());
+        if h.abs() < MIN_STEP * time.abs().max(1.0) {
+            return Err(OrbitError::NoConvergence {
+                iterations: solution.steps.len() + solution.rejected,
+                residual: error,
+            });
+        }
+
+        let tableau = tableau(settings.method);
+        let attempt = match settings.method {
+            Method::Rk4 => doubling_step(&rate_of, time, &current, &rate, h, settings),
+            _ => embedded_step(&rate_of, tableau, time, &current, &rate, h, settings),
+        };
+        error = attempt.error;
+
+        if error <= 1.0 {
+            let next_time = if last { end } else { time + h };
+            let end_rate = match attempt.stages.last() {
+                Some(stage) if tableau.fsal => stage.clone(),
+                _ => rate_of(next_time, &attempt.state),
+            };
+            let mut accepted = Step {
+                time,
+                step: h,
+                start: current,
+                end: attempt.state.clone(),
+                start_rate: rate,
+                end_rate: end_rate.clone(),
+                extension: Extension::Cubic,
+            };
+            accepted.extension = match (settings.method, &attempt.midpoint) {
+                (Method::DormandPrince54, _) => Extension::DormandPrince(combine(
+                    &DVector::zeros(attempt.state.len()),
+                    &attempt.stages,
+                    &DENSE,
+                    h,
+                )),
+                (_, Some((middle, middle_rate))) => accepted.midpoint(middle, middle_rate),
+                _ => {
+                    let half = h / 2.0;
+                    let middle = combine(
+                        &accepted.start,
+                        &stages(
+                            &rate_of,
+                            tableau,
+                            time,
+                            &accepted.start,
+                            &accepted.start_rate,
+                            half,
+                        ),
+                        tableau.b,
+                        half,
+                    );
+                    let middle_rate = rate_of(time + half, &middle);
+                    accepted.midpoint(&middle, &middle_rate)
+                }
+            };
+            solution.steps.push(accepted);
+            time = next_time;
+            current = attempt.state;
+            rate = end_rate;
+        } else {
+            solution.rejected += 1;
+        }
+
+        let factor = if error.is_finite() {
+            (SAFETY * error.powf(-1.0 / (tableau.order + 1.0))).clamp(MIN_FACTOR, MAX_FACTOR)
+        } else {
+            MIN_FACTOR
+        };
+        step = h.abs() * factor;
+    }
+
+    solution.evaluations = evaluations.get();
+    Ok(solution)
+}
+
+fn tableau(method: Method) -> &'static Tableau {
+    match method {
+        Method::Rk4 => &RK4,
+        Method::DormandPrince54 => &DORMAND_PRINCE,
+        Method::Rk87 => &FEHLBERG,
+    }
+}
+
+/* One step of an embedded pair */
+fn embedded_step<F>(
+    rate_of: &F,
+    tableau: &Tableau,
+    time: f64,
+    state: &DVector<f64>,
+    rate: &DVector<f64>,
+    step: f64,
+    settings: &IntegratorSettings,
+) -> Attempt
+where
+    F: Fn(f64, &DVector<f64>) -> DVector<f64>,
+{
+    let stages = stages(rate_of, tableau, time, state, rate, step);
+    let next = combine(state, &stages, tableau.b, step);
+    let difference = combine(&DVector::zeros(state.len()), &stages, tableau.e, step);
+    Attempt {
+        error: error_norm(&difference, state, &next, settings),
+        state: next,
+        stages,
+        midpoint: None,
+    }
+}
+
+/* Classical RK4 with the error estimated from one full step against two
+ * half steps, keeping the two half steps */
+fn doubling_step<F>(
+    rate_of: &F,
+    time: f64,
+    state: &DVector<f64>,
+    rate: &DVector<f64>,
+    step: f64,
+    settings: &IntegratorSettings,
+) -> Attempt
+where
+    F: Fn(f64, &DVector<f64>) -> DVector<f64>,
+{
+    let full = combine(
+        state,
+        &stages(rate_of, &RK4, time, state, rate, step),
+        RK4.b,
+        step,
+    );
+    let half = step / 2.0;
+    let middle = combine(
+        state,
+        &stages(rate_of, &RK4, time, state, rate, half),
+        RK4.b,
+        half,
+    );
+    let middle_rate = rate_of(time + half, &middle);
+    let next = combine(
+        &middle,
+        &stages(rate_of, &RK4, time + half, &middle, &middle_rate, half),
+        RK4.b,
+        half,
+    );
+    // Both are fourth order, so the difference is 15 times the error left
+    // in the half steps
+    let difference = (&next - full) / 15.0;
+    Attempt {
+        error: error_norm(&difference, state, &next, settings),
+        state: next,
+        stages: Vec::new(),
+        midpoint: Some((middle, middle_rate)),
+    }
+}
+
+/* Every stage derivative of a step, with the derivative at the start
+ * already known */
+fn stages<F>(
+    rate_of: &F,
+    tableau: &Tableau,
+    time: f64,
+    state: &DVector<f64>,
+    rate: &DVector<f64>,
+    step: f64,
+) -> Vec<DVector<f64>>
+where
+    F: Fn(f64, &DVector<f64>) -> DVector<f64>,
+{
+    let mut stages = Vec::with_capacity(tableau.c.len());
+    stages.push(rate.clone());
+    for (row, c) in tableau.a.iter().zip(&tableau.c[1..]) {
+        let stage_state = combine(state, &stages, row, step);
+        stages.push(rate_of(time + c * step, &stage_state));
+    }
+    stages
+}
+
+/* state + step * sum(weights[i] * stages[i]) */
+fn combine(
+    state: &DVector<f64>,
+    stages: &[DVector<f64>],
+    weights: &[f64],
+    step: f64,
+) -> DVector<f64> {
+    let mut total = state.clone();
+    for (weight, stage) in weights.iter().zip(stages) {
+        if *weight != 0.0 {
+            total.axpy(step * weight, stage, 1.0);
+        }
+    }
+    total
+}
+
+/* Root mean square of the error over each component's allowed error */
+fn error_norm(
+    error: &DVector<f64>,
+    before: &DVector<f64>,
+    after: &DVector<f64>,
+    settings: &IntegratorSettings,
+) -> f64 {
+    let sum: f64 = (0..error.len())
+        .map(|i| {
+            let scale = settings.absolute_tolerance
+                + settings.tolerance * before[i].abs().max(after[i].abs());
+            (error[i] / scale).powi(2)
+        })
+        .sum();
+    (sum / error.len().max(1) as f64).sqrt()
+}
+
+/* Starting step that moves the state by about a hundredth of itself,
+ * measured against the tolerances (Hairer, Norsett and Wanner II.4) */
+fn initial_step(state: &DVector<f64>, rate: &DVector<f64>, settings: &IntegratorSettings) -> f64 {
+    let size = error_norm(state, state, state, settings);
+    let change = error_norm(rate, state, state, settings);
+    if size < 1e-5 || change < 1e-5 {
+        1e-6
+    } else {
+        0.01 * size / change
+    }
+}
+
+/// Position and velocity as the six element state the integrator works on
+pub(crate) fn pack(position: &Vector3<f64>, velocity: &Vector3<f64>) -> DVector<f64> {
+    DVector::from_iterator(6, position.iter().chain(velocity.iter()).cloned())
+}
+
+/// Position and velocity back out of the first six elements of a state
+pub(crate) fn unpack(state: &DVector<f64>) -> (Vector3<f64>, Vector3<f64>) {
+    (
+        Vector3::new(state[0], state[1], state[2]),
+        Vector3::new(state[3], state[4], state[5]),
+    )
+}
+
+impl Body {
+    /// Integrate the body's state under a force model for duration days,
+    /// which can be negative. The model gives the whole acceleration, so
+    /// pass TwoBody (on its own or along with perturbations) for the
+    /// central body's gravity.
+    pub fn integrate<M: ForceModel>(
+        &self,
+        model: &M,
+        duration: f64,
+        settings: &IntegratorSettings,
+    ) -> Result<Solution, OrbitError> {
+        integrate(
+            |time, state| {
+                let (position, velocity) = unpack(state);
+                pack(&velocity, &model.acceleration(time, &position, &velocity))
+            },
+            0.0,
+            pack(&self.position, &self.velocity),
+            duration,
+            settings,
+        )
+    }
+
+    /// Trajectory through every epoch in times (days from now) under a
+    /// force model. The integrator only runs once each way, out to the
+    /// latest and back to the earliest epoch, and fills in the rest.
+    pub fn propagate_numerical<M, I>(
+        &self,
+        model: &M,
+        times: I,
+        settings: &IntegratorSettings,
+    ) -> Result<Trajectory, OrbitError>
+    where
+        M: ForceModel,
+        I: IntoIterator<Item = f64>,
+    {
+        let times: Vec<f64> = times.into_iter().collect();
+        let latest = times.iter().cloned().fold(0.0, f64::max);
+        let earliest = times.iter().cloned().fold(0.0, f64::min);
+        let forward = self.integrate(model, latest, settings)?;
+        let backward = self.integrate(model, earliest, settings)?;
+
+        let states = times
+            .iter()
+            .map(|&time| {
+                let solution = if time < 0.0 { &backward } else { &forward };
+                let (position, velocity) =
+                    unpack(&solution.state_at(time).ok_or(OrbitError::DegenerateState)?);
+                Ok(State {
+                    time,
+                    position,
+                    velocity,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Trajectory {
+            mu: self.mu,
+            states,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::SOLARGM;
+    use crate::force::TwoBody;
+
+    const METHODS: [Method; 3] = [Method::Rk4, Method::DormandPrince54, Method::Rk87];
+
+    fn orbit() -> Body {
+        // About 1.5 AU with e around 0.4, slightly inclined
+        Body::new(
+            Vector3::new(0.9, 0.1, 0.0),
+            Vector3::new(-0.004, 0.021, 0.001),
+            SOLARGM,
+        )
+    }
+
+    /* Largest relative position error of the solution against the analytic
+     * two-body state at each time, with body the state at epoch */
+    fn worst_error(body: &Body, epoch: f64, solution: &Solution, times: &[f64]) -> f64 {
+        times
+            .iter()
+            .map(|&time| {
+                let (position, _) = unpack(&solution.state_at(time).unwrap());
+                let expected = body.position_at_time(time - epoch).unwrap();
+                (position - expected).norm() / expected.norm()
+            })
+            .fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn methods_match_two_body() {
+        let body = orbit();
+        let duration = 2.0 * body.orbital_period();
+        for &method in &METHODS {
+            let settings = IntegratorSettings {
+                tolerance: 1e-11,
+                absolute_tolerance: 1e-14,
+                ..IntegratorSettings::new(method)
+            };
+            let solution = body
+                .integrate(&TwoBody::new(SOLARGM), duration, &settings)
+                .unwrap();
+            let error = worst_error(&body, 0.0, &solution, &[duration]);
+            assert!(error < 1e-7, "{:?} off by {:e}", method, error);
+        }
+    }
+
+    #[test]
+    fn backward_matches_two_body() {
+        let body = orbit();
+        for &method in &METHODS {
+            let settings = IntegratorSettings {
+                tolerance: 1e-11,
+                absolute_tolerance: 1e-14,
+                ..IntegratorSettings::new(method)
+            };
+            let solution = body
+                .integrate(&TwoBody::new(SOLARGM), -400.0, &settings)
+                .unwrap();
+            let error = worst_error(&body, 0.0, &solution, &[-400.0, -123.4]);
+            assert!(error < 1e-7, "{:?} off by {:e}", method, error);
+        }
+    }
+
+    #[test]
+    fn dense_output_between_steps() {
+        let body = orbit();
+        let duration = body.orbital_period();
+        for &method in &METHODS {
+            let settings = IntegratorSettings {
+                tolerance: 1e-11,
+                absolute_tolerance: 1e-14,
+                ..IntegratorSettings::new(method)
+            };
+            let solution = body
+                .integrate(&TwoBody::new(SOLARGM), duration, &settings)
+                .unwrap();
+            // Halfway through every step, as far from the stored states as
+            // it gets, against the exact orbit through the step's start so
+            // the error piled up in earlier steps doesn't count
+            let ends: Vec<f64> = solution.times().collect();
+            for step in ends.windows(2) {
+                let (position, velocity) = unpack(&solution.state_at(step[0]).unwrap());
+                let start = Body::new(position, velocity, SOLARGM);
+                let middle = (step[0] + step[1]) / 2.0;
+                let error = worst_error(&start, step[0], &solution, &[middle]);
+                assert!(
+                    error < 1e-10,
+                    "{:?} interpolation off by {:e}",
+                    method,
+                    error
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn state_at_outside_is_none() {
+        let solution = orbit()
+            .integrate(&TwoBody::new(SOLARGM), 10.0, &IntegratorSettings::default())
+            .unwrap();
+        assert!(solution.state_at(-1.0).is_none());
+        assert!(solution.state_at(10.5).is_none());
+        assert_eq!(
+            solution.state_at(0.0).unwrap(),
+            pack(&orbit().position, &orbit().velocity)
+        );
+    }
+}
@@ -0,0 +1,299 @@
+/*!
+ * Kepler's equation for elliptic and hyperbolic orbits. The single step
+ * functions are kept public since the homework problems ask for the first
+ * few Newton iterations by hand. Everything else goes through
+ * solve_elliptic and solve_hyperbolic, which start from a good guess, are
+ * capped at a maximum number of iterations and report how the solve went.
+ */
+use std::f64::consts::PI;
+
+use super::error::OrbitError;
+
+const TOLERANCE: f64 = 1e-12;
+const MAX_ITERATIONS: usize = 50;
+
+/// Iteration scheme used by the solvers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Method {
+    Newton,
+    Halley,
+    /// Laguerre-Conway, converges from practically any starting guess
+    LaguerreConway,
+}
+
+/// Tolerance, iteration cap and method for the Kepler solvers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SolverSettings {
+    pub tolerance: f64,
+    pub max_iterations: usize,
+    pub method: Method,
+}
+
+impl Default for SolverSettings {
+    fn default() -> SolverSettings {
+        SolverSettings {
+            tolerance: TOLERANCE,
+            max_iterations: MAX_ITERATIONS,
+            method: Method::LaguerreConway,
+        }
+    }
+}
+
+/// Result of solving Kepler's equation. residual is |f(anomaly)|, the
+/// error left in Kepler's equation itself.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeplerSolution {
+    pub anomaly: f64,
+    pub iterations: usize,
+    pub residual: f64,
+    pub converged: bool,
+}
+
+impl KeplerSolution {
+    /// The anomaly if the solver converged, otherwise an error carrying the
+    /// iteration count and residual
+    pub fn into_result(self) -> Result<f64, OrbitError> {
+        if self.converged {
+            Ok(self.anomaly)
+        } else {
+            Err(OrbitError::NoConvergence {
+                iterations: self.iterations,
+                residual: self.residual,
+            })
+        }
+    }
+}
+
+/// Eccentric anomaly for a mean anomaly on an elliptic orbit (0 <= e < 1)
+pub fn solve_elliptic(nt: f64, eccen: f64, settings: &SolverSettings) -> KeplerSolution {
+    // Solve for M in [-pi, pi) and add the whole revolutions back at the end
+    let revs = ((nt + PI) / (2.0 * PI)).floor();
+    let m = nt - revs * 2.0 * PI;
+    let kep = |e: f64| {
+        (
+            e - eccen * e.sin() - m,
+            1.0 - eccen * e.cos(),
+            eccen * e.sin(),
+        )
+    };
+
+    // Danby's starting guess
+    let init = m + 0.85 * eccen * m.signum();
+    let mut solution = iterate(kep, init, settings);
+    solution.anomaly += revs * 2.0 * PI;
+    solution
+}
+
+/// Hyperbolic anomaly for a mean anomaly on a hyperbolic orbit (e > 1)
+pub fn solve_hyperbolic(nt: f64, eccen: f64, settings: &SolverSettings) -> KeplerSolution {
+    let kep = |f: f64| {
+        (
+            eccen * f.sinh() - f - nt,
+            eccen * f.cosh() - 1.0,
+            eccen * f.sinh(),
+        )
+    };
+    let init = nt.signum() * (2.0 * nt.abs() / eccen + 1.8).ln();
+    iterate(kep, init, settings)
+}
+
+/* Shared iteration loop. kep returns f, f' and f'' at a point. */
+fn iterate<F>(kep: F, init: f64, settings: &SolverSettings) -> KeplerSolution
+where
+    F: Fn(f64) -> (f64, f64, f64),
+{
+    let mut anomaly = init;
+    let mut iterations = 0;
+    let mut converged = false;
+    while iterations < settings.max_iterations {
+        let (f, f_d, f_dd) = kep(anomaly);
+        let delta = match settings.method {
+            Method::Newton => f / f_d,
+            Method::Halley => 2.0 * f * f_d / (2.0 * f_d.powi(2) - f * f_dd),
+            Method::LaguerreConway => {
+                let n: f64 = 5.0;
+                let root = ((n - 1.0).powi(2) * f_d.powi(2) - n * (n - 1.0) * f * f_dd)
+                    .abs()
+                    .sqrt();
+                n * f / (f_d + f_d.signum() * root)
+            }
+        };
+        anomaly -= delta;
+        iterations += 1;
+        if delta.abs() < settings.tolerance {
+            converged = true;
+            break;
+        }
+    }
+    KeplerSolution {
+        anomaly,
+        iterations,
+        residual: kep(anomaly).0.abs(),
+        converged,
+    }
+}
+
+/// Newton correction for the elliptic Kepler equation, E - e sin(E) = M
+pub fn delta_e(e: f64, nt: f64, eccen: f64) -> f64 {
+    (e - eccen * e.sin() - nt) / (1.0 - eccen * e.cos())
+}
+
+/// One Newton iteration of the elliptic Kepler equation starting from init
+pub fn kepler_step(init: f64, nt: f64, eccen: f64) -> f64 {
+    init - delta_e(init, nt, eccen)
+}
+
+/// Iterate the elliptic Kepler equation with Newton's method from init.
+/// Gives up with NoConvergence after MAX_ITERATIONS steps.
+pub fn kepler_iterate(init: f64, nt: f64, eccen: f64) -> Result<f64, OrbitError> {
+    newton_iterate(
+        init,
+        |e| kepler_step(e, nt, eccen),
+        |e| e - eccen * e.sin() - nt,
+    )
+}
+
+/// Newton correction for the hyperbolic Kepler equation, e sinh(F) - F = M
+pub fn hyper_delta_e(e: f64, nt: f64, eccen: f64) -> f64 {
+    let numer = eccen * e.sinh() - nt - e;
+    let denom = eccen * e.cosh() - 1.0;
+    numer / denom
+}
+
+/// One Newton iteration of the hyperbolic Kepler equation starting from init
+pub fn hyper_kepler_step(init: f64, nt: f64, eccen: f64) -> f64 {
+    init - hyper_delta_e(init, nt, eccen)
+}
+
+/// Iterate the hyperbolic Kepler equation with Newton's method from init.
+/// Gives up with NoConvergence after MAX_ITERATIONS steps.
+pub fn hyper_kepler_iterate(init: f64, nt: f64, eccen: f64) -> Result<f64, OrbitError> {
+    newton_iterate(
+        init,
+        |e| hyper_kepler_step(e, nt, eccen),
+        |e| eccen * e.sinh() - e - nt,
+    )
+}
+
+/* Take Newton steps from init until they stop moving the anomaly.
+ * residual is the left over error in Kepler's equation, for the report
+ * when it doesn't converge. */
+fn newton_iterate<S, R>(init: f64, step: S, residual: R) -> Result<f64, OrbitError>
+where
+    S: Fn(f64) -> f64,
+    R: Fn(f64) -> f64,
+{
+    let mut e_0 = init;
+    for _ in 0..MAX_ITERATIONS {
+        let e = step(e_0);
+        if (e - e_0).abs() <= TOLERANCE {
+            return Ok(e);
+        }
+        e_0 = e;
+    }
+    Err(OrbitError::NoConvergence {
+        iterations: MAX_ITERATIONS,
+        residual: residual(e_0).abs(),
+    })
+}
+
+/// Eccentric anomaly for a given mean anomaly on an elliptic orbit
+pub fn elliptic_kepler(nt: f64, eccen: f64) -> Result<f64, OrbitError> {
+    solve_elliptic(nt, eccen, &SolverSettings::default()).into_result()
+}
+
+/// Hyperbolic anomaly for a given mean anomaly on a hyperbolic orbit
+pub fn hyper_kepler(nt: f64, eccen: f64) -> Result<f64, OrbitError> {
+    solve_hyperbolic(nt, eccen, &SolverSettings::default()).into_result()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const METHODS: [Method; 3] = [Method::Newton, Method::Halley, Method::LaguerreConway];
+
+    fn settings(method: Method) -> SolverSettings {
+        SolverSettings {
+            method,
+            ..SolverSettings::default()
+        }
+    }
+
+    #[test]
+    fn elliptic_converges_near_parabolic() {
+        let eccen = 0.99;
+        for &method in &METHODS {
+            for &nt in &[1e-3, 0.05, 1.0, 3.1, -2.0, 20.0] {
+                let solution = solve_elliptic(nt, eccen, &settings(method));
+                assert!(solution.converged, "{:?} at M = {}", method, nt);
+                assert!(solution.residual < 1e-12);
+                let e = solution.anomaly;
+                assert!((e - eccen * e.sin() - nt).abs() < 1e-11);
+            }
+        }
+    }
+
+    #[test]
+    fn hyperbolic_converges() {
+        for &method in &METHODS {
+            for &eccen in &[1.01, 1.73, 5.0] {
+                for &nt in &[1e-3, 0.5, 10.0, -40.0, 600.0] {
+                    let solution = solve_hyperbolic(nt, eccen, &settings(method));
+                    assert!(
+                        solution.converged,
+                        "{:?} at e = {}, M = {}",
+                        method, eccen, nt
+                    );
+                    let f = solution.anomaly;
+                    assert!((eccen * f.sinh() - f - nt).abs() < 1e-10 * nt.abs().max(1.0));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn iteration_cap_reports_no_convergence() {
+        let capped = SolverSettings {
+            max_iterations: 1,
+            method: Method::Newton,
+            ..SolverSettings::default()
+        };
+        let solution = solve_elliptic(2.0, 0.99, &capped);
+        assert!(!solution.converged);
+        assert_eq!(solution.iterations, 1);
+        match solution.into_result() {
+            Err(OrbitError::NoConvergence {
+                iterations,
+                residual,
+            }) => {
+                assert_eq!(iterations, 1);
+                assert!(residual > 0.0);
+            }
+            other => panic!("expected NoConvergence, got {:?}", other),
+        }
+        assert!(solve_hyperbolic(600.0, 1.01, &capped)
+            .into_result()
+            .is_err());
+    }
+
+    #[test]
+    fn newton_iterations_match_the_solvers() {
+        let elliptic = kepler_iterate(0.0, 17.0 * PI / 4.0, 0.3).unwrap();
+        assert!((elliptic - elliptic_kepler(17.0 * PI / 4.0, 0.3).unwrap()).abs() < 1e-12);
+        let hyperbolic = hyper_kepler_iterate(0.0, 3.0 * PI / 4.0, 1.73).unwrap();
+        assert!((hyperbolic - hyper_kepler(3.0 * PI / 4.0, 1.73).unwrap()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn newton_iterations_report_a_bad_start() {
+        // Starting at F = 0 for a large mean anomaly overshoots so far that
+        // sinh overflows
+        match hyper_kepler_iterate(0.0, 76.0 * PI, 1.73) {
+            Err(OrbitError::NoConvergence { iterations, .. }) => {
+                assert_eq!(iterations, MAX_ITERATIONS)
+            }
+            other => panic!("expected NoConvergence, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,320 @@
+/*!
+ * Lambert's problem: find the orbit that goes from r_0 to r in a given time
+ * of flight. Same universal-variable bisection on psi as the C++ version in
+ * the final project (Vallado, Algorithm 58), with c2 and c3 taken from the
+ * universal module so negative psi (hyperbolic transfers) works too, and
+ * extended to transfers that make whole revolutions before arriving.
+ *
+ * For n revolutions psi lives in (4 pi^2 n^2, 4 pi^2 (n + 1)^2). The time
+ * of flight blows up at both ends of that interval, so there's a minimum
+ * time in between and two solutions for every longer time: the left branch
+ * below the minimum and the right branch above it.
+ */
+use nalgebra::Vector3;
+use std::f64::consts::PI;
+
+use super::body::Body;
+use super::error::OrbitError;
+use super::universal::{c2_coeff, c3_coeff};
+
+/* Relative tolerance on the time of flight */
+const TOLERANCE: f64 = 1e-12;
+const MAX_ITERATIONS: usize = 200;
+
+/* Golden ratio step for finding the minimum time of flight */
+const GOLDEN: f64 = 0.618_033_988_749_894_9;
+
+/// Which way around the central body the transfer goes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    /// Transfer angle under 180 degrees
+    Short,
+    /// Transfer angle over 180 degrees
+    Long,
+}
+
+/// Which of the two multi-revolution solutions to take. Ignored when there
+/// are no whole revolutions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Branch {
+    /// psi below the minimum time of flight point
+    Left,
+    /// psi above the minimum time of flight point
+    Right,
+}
+
+/// The transfer orbit at both ends. departure has r_0 and the velocity
+/// needed to leave it, arrival has r and the velocity it's reached with.
+#[derive(Debug, Clone)]
+pub struct LambertSolution {
+    pub departure: Body,
+    pub arrival: Body,
+    pub iterations: usize,
+}
+
+/// Solve Lambert's problem with no whole revolutions. The time of flight is
+/// in days and mu is in AU^3/day^2, like Body.
+pub fn lambert(
+    r_0: Vector3<f64>,
+    r: Vector3<f64>,
+    time_of_flight: f64,
+    mu: f64,
+    direction: Direction,
+) -> Result<LambertSolution, OrbitError> {
+    lambert_multi_rev(r_0, r, time_of_flight, mu, direction, 0, Branch::Left)
+}
+
+/// Solve Lambert's problem for a transfer that makes a number of whole
+/// revolutions before arriving
+pub fn lambert_multi_rev(
+    r_0: Vector3<f64>,
+    r: Vector3<f64>,
+    time_of_flight: f64,
+    mu: f64,
+    direction: Direction,
+    revolutions: u32,
+    branch: Branch,
+) -> Result<LambertSolution, OrbitError> {
+    let r_0_mag = r_0.norm();
+    let r_mag = r.norm();
+    let finite = r_0.iter().chain(r.iter()).all(|x| x.is_finite());
+    if !finite || r_0_mag == 0.0 || r_mag == 0.0 || mu <= 0.0 {
+        return Err(OrbitError::DegenerateState);
+    }
+    if time_of_flight <= 0.0 || !time_of_flight.is_finite() {
+        return Err(OrbitError::NoSolution);
+    }
+
+    let cos_delta_tht = r_0.dot(&r) / (r_mag * r_0_mag);
+    let tm = match direction {
+        Direction::Short => 1.0,
+        Direction::Long => -1.0,
+    };
+    let a = tm * (r_mag * r_0_mag * (1.0 + cos_delta_tht)).max(0.0).sqrt();
+
+    // Positions 180 degrees apart don't pick out a transfer plane, and the
+    // same direction at both ends only works on a straight line
+    if a.abs() < 1e-12 * (r_mag + r_0_mag) || cos_delta_tht > 1.0 - 1e-14 {
+        return Err(OrbitError::Collinear);
+    }
+
+    let sqrt_mu = mu.sqrt();
+
+    // y and the time of flight for a value of psi, None where y goes
+    // negative and there's no real solution
+    let flight = |psi: f64| -> Option<(f64, f64)> {
+        let c2 = c2_coeff(psi);
+        let c3 = c3_coeff(psi);
+        let y = r_0_mag + r_mag + a * (psi * c3 - 1.0) / c2.sqrt();
+        if y < 0.0 || !y.is_finite() {
+            return None;
+        }
+        let x = (y / c2).sqrt();
+        Some((y, (x.powi(3) * c3 + a * y.sqrt()) / sqrt_mu))
+    };
+
+    let n = f64::from(revolutions);
+    let (mut lower, mut upper, rising) = if revolutions == 0 {
+        (-4.0 * PI, 4.0 * PI.powi(2), true)
+    } else {
+        let start = 4.0 * PI.powi(2) * n.powi(2);
+        let end = 4.0 * PI.powi(2) * (n + 1.0).powi(2);
+        let psi_min = minimum_time(&flight, start, end);
+        match flight(psi_min) {
+            Some((_, t_min)) if t_min <= time_of_flight => {}
+            _ => return Err(OrbitError::NoSolution),
+        }
+        match branch {
+            Branch::Left => (start, psi_min, false),
+            Branch::Right => (psi_min, end, true),
+        }
+    };
+
+    // On the zero revolution branch the time of flight keeps shrinking as
+    // psi goes negative, so push the lower bound down until it's bracketed
+    if revolutions == 0 {
+        while matches!(flight(lower), Some((_, t)) if t > time_of_flight) {
+            lower *= 2.0;
+            if lower < -1e12 {
+                return Err(OrbitError::NoSolution);
+            }
+        }
+    }
+
+    // Bisection, with a negative y counting as too short a time of flight
+    // since it only happens at the small psi end of the interval
+    let mut iterations = 0;
+    let mut psi = (upper + lower) / 2.0;
+    let mut residual = f64::INFINITY;
+    while iterations < MAX_ITERATIONS {
+        iterations += 1;
+        psi = (upper + lower) / 2.0;
+        let move_lower = match flight(psi) {
+            Some((_, t)) => {
+                residual = (t - time_of_flight).abs();
+                if residual <= TOLERANCE * time_of_flight {
+                    break;
+                }
+                (t < time_of_flight) == rising
+            }
+            None => true,
+        };
+        if move_lower {
+            lower = psi;
+        } else {
+            upper = psi;
+        }
+    }
+    if residual > TOLERANCE * time_of_flight {
+        return Err(OrbitError::NoConvergence {
+            iterations,
+            residual,
+        });
+    }
+
+    let (y, _) = flight(psi).ok_or(OrbitError::NoSolution)?;
+    let f = 1.0 - y / r_0_mag;
+    let g_dot = 1.0 - y / r_mag;
+    let g = a * (y / mu).sqrt();
+    let v_0 = (r - f * r_0) / g;
+    let v = (g_dot * r - r_0) / g;
+
+    Ok(LambertSolution {
+        departure: Body::new(r_0, v_0, mu),
+        arrival: Body::new(r, v, mu),
+        iterations,
+    })
+}
+
+/* Golden section search for the psi with the shortest time of flight
+ * between start and end. Points where y is negative count as infinitely
+ * long so the search stays out of them. */
+fn minimum_time<F>(flight: &F, start: f64, end: f64) -> f64
+where
+    F: Fn(f64) -> Option<(f64, f64)>,
+{
+    let time = |psi: f64| flight(psi).map_or(f64::INFINITY, |(_, t)| t);
+    let (mut lower, mut upper) = (start, end);
+    let mut left = upper - GOLDEN * (upper - lower);
+    let mut right = lower + GOLDEN * (upper - lower);
+    let (mut t_left, mut t_right) = (time(left), time(right));
+    for _ in 0..MAX_ITERATIONS {
+        if upper - lower < TOLERANCE * end {
+            break;
+        }
+        if t_left < t_right {
+            upper = right;
+            right = left;
+            t_right = t_left;
+            left = upper - GOLDEN * (upper - lower);
+            t_left = time(left);
+        } else {
+            lower = left;
+            left = right;
+            t_left = t_right;
+            right = lower + GOLDEN * (upper - lower);
+            t_right = time(right);
+        }
+    }
+    (upper + lower) / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::SOLARGM;
+    use crate::universal::UniversalPropagator;
+
+    fn departure() -> Vector3<f64> {
+        Vector3::new(1.0, 0.0, 0.0)
+    }
+
+    fn target() -> Vector3<f64> {
+        Vector3::new(-0.3, 1.4, 0.1)
+    }
+
+    /* Fly the departure state for the time of flight and check it lands
+     * on the target with the arrival velocity */
+    fn assert_arrives(solution: &LambertSolution, r: Vector3<f64>, time_of_flight: f64) {
+        let departure = &solution.departure;
+        let (position, velocity) =
+            UniversalPropagator::new(departure.position, departure.velocity, SOLARGM)
+                .unwrap()
+                .state_at(time_of_flight)
+                .unwrap();
+        let miss = (position - r).norm() / r.norm();
+        assert!(miss < 1e-9, "missed the target by {:e}", miss);
+        assert!((solution.arrival.position - r).norm() < 1e-12);
+        let velocity_error = (velocity - solution.arrival.velocity).norm() / velocity.norm();
+        assert!(
+            velocity_error < 1e-9,
+            "arrival velocity off by {:e}",
+            velocity_error
+        );
+    }
+
+    #[test]
+    fn short_way_arrives() {
+        let solution = lambert(departure(), target(), 200.0, SOLARGM, Direction::Short).unwrap();
+        assert_arrives(&solution, target(), 200.0);
+        // Prograde, same sense as the short transfer angle
+        let h = solution.departure.angular_momentum();
+        assert!(h.dot(&departure().cross(&target())) > 0.0);
+    }
+
+    #[test]
+    fn long_way_arrives() {
+        let solution = lambert(departure(), target(), 300.0, SOLARGM, Direction::Long).unwrap();
+        assert_arrives(&solution, target(), 300.0);
+        let h = solution.departure.angular_momentum();
+        assert!(h.dot(&departure().cross(&target())) < 0.0);
+    }
+
+    #[test]
+    fn hyperbolic_transfer_arrives() {
+        let solution = lambert(departure(), target(), 20.0, SOLARGM, Direction::Short).unwrap();
+        assert!(solution.departure.eccentricity() > 1.0);
+        assert_arrives(&solution, target(), 20.0);
+    }
+
+    #[test]
+    fn multi_rev_branches_arrive() {
+        let time_of_flight = 1000.0;
+        let solutions: Vec<_> = [Branch::Left, Branch::Right]
+            .iter()
+            .map(|&branch| {
+                lambert_multi_rev(
+                    departure(),
+                    target(),
+                    time_of_flight,
+                    SOLARGM,
+                    Direction::Short,
+                    1,
+                    branch,
+                )
+                .unwrap()
+            })
+            .collect();
+        for solution in &solutions {
+            assert_arrives(solution, target(), time_of_flight);
+            // A whole revolution means the period is under the time of flight
+            assert!(solution.departure.orbital_period() < time_of_flight);
+        }
+        let difference = solutions[0].departure.velocity - solutions[1].departure.velocity;
+        assert!(difference.norm() > 1e-4);
+    }
+
+    #[test]
+    fn too_fast_for_a_revolution_fails() {
+        let result = lambert_multi_rev(
+            departure(),
+            target(),
+            100.0,
+            SOLARGM,
+            Direction::Short,
+            1,
+            Branch::Left,
+        );
+        assert_eq!(result.unwrap_err(), OrbitError::NoSolution);
+    }
+}
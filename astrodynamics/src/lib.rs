@@ -0,0 +1,53 @@
+/*!
+ * Austen LeBeau
+ *
+ * Shared orbital mechanics library. Every homework and exam binary pulls
+ * Body, OrbitType, the Kepler solvers and the date/printing helpers from
+ * here instead of keeping its own copy, so a fix made once shows up in all
+ * of them.
+ *
+ * Conventions used throughout the library:
+ *   - angles are in radians
+ *   - Body states are in AU and AU/day, relative to a central body whose
+ *     gravitational parameter (AU^3/day^2) the Body carries as mu
+ *   - the types in units convert to and from those at the edges, so
+ *     binaries never multiply by conversion constants themselves
+ *   - the orbit frame built by Body::make_frame is (e_r, e_theta, e_h)
+ */
+
+#[macro_use]
+pub mod macros;
+pub mod body;
+pub mod constants;
+pub mod date;
+pub mod drag;
+pub mod elements;
+pub mod encke;
+pub mod equinoctial;
+pub mod error;
+pub mod flyby;
+pub mod force;
+pub mod integrator;
+pub mod kepler;
+pub mod lambert;
+pub mod maneuver;
+pub mod nbody;
+pub mod oblateness;
+pub mod patched_conic;
+pub mod plane_change;
+pub mod planet;
+pub mod porkchop;
+pub mod radiation;
+pub mod stm;
+pub mod symplectic;
+pub mod third_body;
+pub mod trajectory;
+pub mod transfer;
+pub mod units;
+pub mod universal;
+
+pub use body::{three_one_three_transform, transform, Body, OrbitType};
+pub use elements::KeplerianElements;
+pub use equinoctial::{EquinoctialElements, ModifiedEquinoctialElements};
+pub use error::OrbitError;
+pub use trajectory::{State, Trajectory};
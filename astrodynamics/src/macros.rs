@@ -1,13 +1,13 @@
-/**
+/*!
  * Macros that make some operations more convenient.
  */
-// use super::date::*;
 
 pub fn underline(string: &str) -> String {
     format!("{}\n{}", string, "-".repeat(string.len()))
 }
 
 /* Macro that makes it more convenient to print out results */
+#[macro_export]
 macro_rules! printer {
     // print vector
     ($msg:expr, v => $val:expr) => {
@@ -39,6 +39,7 @@ macro_rules! printer {
     };
 }
 
+#[macro_export]
 macro_rules! date {
     // Going from Gregorian date to Julian date. It has the same date format as Horizons
     ($year:literal-$month:literal-$day:literal $hours:literal:$minutes:literal:$seconds:literal) => {
@@ -47,11 +48,11 @@ macro_rules! date {
             + ((275.0 * $month as f64) / 9.0).trunc()
             + 1_721_013.5
             + $day as f64
-            + ((((($seconds as f64 / 60.0) + $minutes as f64) / 60.0) + $hours as f64) / 24.0);
+            + ((((($seconds as f64 / 60.0) + $minutes as f64) / 60.0) + $hours as f64) / 24.0)
     };
     // Going from Julian to Gregorian, which needs it's own function since macros don't like
     // lets when you're trying to return data from the macro
     ($julian:expr) => {
-        date::julian_to_greg($julian);
+        $crate::date::julian_to_greg($julian)
     };
 }
@@ -0,0 +1,161 @@
+/*!
+ * Impulsive maneuvers given in a frame attached to the orbit instead of
+ * in inertial coordinates. Applying one gives back a new Body, with its
+ * OrbitType worked out again, and a record of the burn for reporting.
+ */
+use nalgebra::{Matrix3, Vector3};
+
+use super::body::{Body, OrbitType};
+use super::transfer::Burn;
+
+/// Frame a delta-v is given in. Each one is (x, y, z) in that order.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Frame {
+    Inertial,
+    /// Radial, transverse and normal, (e_r, e_theta, e_h) from make_frame
+    Rtn,
+    /// Along the velocity, along the angular momentum (normal to the
+    /// orbit plane) and the binormal that completes the set
+    Vnb,
+}
+
+impl Frame {
+    /// Rows are the frame's unit vectors in inertial coordinates, so this
+    /// takes inertial vectors into the frame and its transpose takes them
+    /// back out
+    pub fn matrix(self, body: &Body) -> Matrix3<f64> {
+        match self {
+            Frame::Inertial => Matrix3::identity(),
+            Frame::Rtn => body.make_frame(),
+            Frame::Vnb => {
+                let e_v = body.velocity.normalize();
+                let e_n = body.angular_momentum().normalize();
+                let e_b = e_v.cross(&e_n);
+                Matrix3::from_rows(&[e_v.transpose(), e_n.transpose(), e_b.transpose()])
+            }
+        }
+    }
+}
+
+/// Record of a maneuver: the burn as it was asked for, the same burn in
+/// inertial coordinates, and the orbit before and after
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Maneuver {
+    pub frame: Frame,
+    pub delta_v: Vector3<f64>,
+    pub inertial_delta_v: Vector3<f64>,
+    pub position: Vector3<f64>,
+    pub velocity_before: Vector3<f64>,
+    pub velocity_after: Vector3<f64>,
+    pub orbit_before: OrbitType,
+    pub orbit_after: OrbitType,
+}
+
+impl Maneuver {
+    pub fn magnitude(&self) -> f64 {
+        self.delta_v.norm()
+    }
+
+    /// The maneuver as a burn made time days from the start of a transfer
+    pub fn to_burn(&self, time: f64) -> Burn {
+        Burn {
+            time,
+            delta_v: self.inertial_delta_v,
+        }
+    }
+}
+
+impl std::fmt::Display for Maneuver {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "\
+             Frame:                 {:?}\n\
+             Delta-v:               {:.10e} {:.10e} {:.10e}\n\
+             Inertial Delta-v:      {:.10e} {:.10e} {:.10e}\n\
+             Magnitude:             {:.10e}\n\
+             Orbit:                 {:?} -> {:?}",
+            self.frame,
+            self.delta_v[0],
+            self.delta_v[1],
+            self.delta_v[2],
+            self.inertial_delta_v[0],
+            self.inertial_delta_v[1],
+            self.inertial_delta_v[2],
+            self.magnitude(),
+            self.orbit_before,
+            self.orbit_after
+        )
+    }
+}
+
+impl Body {
+    /// Apply an impulsive delta-v given in frame, returning the body right
+    /// after the burn and a record of the maneuver
+    pub fn apply_delta_v(&self, delta_v: Vector3<f64>, frame: Frame) -> (Body, Maneuver) {
+        let inertial_delta_v = frame.matrix(self).transpose() * delta_v;
+        let after = Body::new(self.position, self.velocity + inertial_delta_v, self.mu);
+        let maneuver = Maneuver {
+            frame,
+            delta_v,
+            inertial_delta_v,
+            position: self.position,
+            velocity_before: self.velocity,
+            velocity_after: after.velocity,
+            orbit_before: self.orbit_type,
+            orbit_after: after.orbit_type,
+        };
+        (after, maneuver)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::SOLARGM;
+    use crate::elements::KeplerianElements;
+
+    fn circular() -> Body {
+        let elements = KeplerianElements::from_degrees(1.2, 0.0, 30.0, 0.0, 40.0, 75.0);
+        Body::from_elements(&elements, SOLARGM).unwrap()
+    }
+
+    #[test]
+    fn along_track_burns_agree_on_a_circular_orbit() {
+        let body = circular();
+        let speed = 1e-3;
+        let (vnb, v_burn) = body.apply_delta_v(Vector3::new(speed, 0.0, 0.0), Frame::Vnb);
+        let (rtn, t_burn) = body.apply_delta_v(Vector3::new(0.0, speed, 0.0), Frame::Rtn);
+        let expected = speed * body.velocity.normalize();
+        assert!((v_burn.inertial_delta_v - expected).norm() < 1e-15);
+        assert!((t_burn.inertial_delta_v - expected).norm() < 1e-15);
+        assert!((vnb.velocity - rtn.velocity).norm() < 1e-15);
+        assert_eq!(v_burn.orbit_before, body.orbit_type);
+        assert_eq!(v_burn.orbit_after, OrbitType::Elliptic);
+        assert_eq!(v_burn.to_burn(3.0).delta_v, v_burn.inertial_delta_v);
+    }
+
+    #[test]
+    fn normal_burn_only_tilts_the_plane() {
+        let body = circular();
+        let speed = 1e-4;
+        let (after, maneuver) = body.apply_delta_v(Vector3::new(0.0, speed, 0.0), Frame::Vnb);
+        let (rtn, _) = body.apply_delta_v(Vector3::new(0.0, 0.0, speed), Frame::Rtn);
+        assert!((after.velocity - rtn.velocity).norm() < 1e-15);
+        assert_eq!(maneuver.position, body.position);
+
+        // The plane turns about the position vector, so the inclination
+        // and node move at first order in the burn
+        let ratio = speed / body.velocity.norm();
+        assert!(after.angular_momentum().dot(&body.position).abs() < 1e-15);
+        assert!((after.inclination() - body.inclination()).abs() > 0.1 * ratio);
+        assert!(
+            (after.argument_of_ascending_node() - body.argument_of_ascending_node()).abs()
+                > 0.1 * ratio
+        );
+        // while the size and shape only pick up the burn's square
+        let a_change = (after.semi_major_axis() - body.semi_major_axis()).abs();
+        assert!(a_change / body.semi_major_axis() < 3.0 * ratio.powi(2));
+        assert!(after.eccentricity() < 3.0 * ratio.powi(2));
+    }
+}
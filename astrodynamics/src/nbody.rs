@@ -0,0 +1,420 @@
+/*!
+ * N-body simulation. Every body pulls on every other one, so unlike Body,
+ * which moves on a fixed conic about a central body, nothing here has a
+ * central body at all; the Sun is just another entry in the list.
+ *
+ * Masses are carried as gravitational parameters (AU^3/day^2), which is all
+ * the equations of motion need. The energy and momenta reported are the
+ * usual ones multiplied through by G, so they only mean something compared
+ * against themselves, which is what the drift reporting does: the exact
+ * solution conserves all three, so any change is integration error.
+ */
+use nalgebra::{DVector, Vector3};
+
+use super::body::Body;
+use super::error::OrbitError;
+use super::integrator::{integrate, IntegratorSettings, Solution};
+use super::trajectory::{State, Trajectory};
+
+/// One body of an N-body system, in an inertial frame
+#[derive(Debug, Clone, PartialEq)]
+pub struct Particle {
+    pub name: String,
+    /// Gravitational parameter, AU^3/day^2
+    pub gm: f64,
+    pub position: Vector3<f64>,
+    pub velocity: Vector3<f64>,
+}
+
+impl Particle {
+    pub fn new(name: &str, gm: f64, position: Vector3<f64>, velocity: Vector3<f64>) -> Particle {
+        Particle {
+            name: String::from(name),
+            gm,
+            position,
+            velocity,
+        }
+    }
+
+    /// Particle from a Body's state, which is taken as inertial with the
+    /// Body's central body sitting still at the origin
+    pub fn from_body(name: &str, gm: f64, body: &Body) -> Particle {
+        Particle::new(name, gm, body.position, body.velocity)
+    }
+
+    /// State relative to another particle as a Body orbiting it
+    pub fn relative_to(&self, primary: &Particle) -> Body {
+        Body::new(
+            self.position - primary.position,
+            self.velocity - primary.velocity,
+            primary.gm + self.gm,
+        )
+    }
+}
+
+/// Total energy, linear momentum and angular momentum of a set of bodies,
+/// all multiplied by G
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Invariants {
+    pub energy: f64,
+    pub momentum: Vector3<f64>,
+    pub angular_momentum: Vector3<f64>,
+}
+
+impl Invariants {
+    pub fn new(particles: &[Particle]) -> Invariants {
+        let mut energy = 0.0;
+        let mut momentum = Vector3::zeros();
+        let mut angular_momentum = Vector3::zeros();
+        for (i, particle) in particles.iter().enumerate() {
+            energy += 0.5 * particle.gm * particle.velocity.norm_squared();
+            for other in &particles[i + 1..] {
+                energy -= particle.gm * other.gm / (particle.position - other.position).norm();
+            }
+            momentum += particle.gm * particle.velocity;
+            angular_momentum += particle.gm * particle.position.cross(&particle.velocity);
+        }
+        Invariants {
+            energy,
+            momentum,
+            angular_momentum,
+        }
+    }
+
+    /// How far these have moved from a starting set, each as a fraction
+    /// of how big its terms are for particles rather than of its starting
+    /// value, since any of them can start out at zero (linear momentum in
+    /// the barycentric frame, angular momentum when everything moves
+    /// radially, energy right at escape). Energy is relative to the kinetic
+    /// energy plus the size of the potential, linear momentum to the total
+    /// of every |gm v| and angular momentum to the total of every
+    /// gm |r| |v|. A scale of zero leaves the drift absolute.
+    pub fn drift_from(&self, initial: &Invariants, particles: &[Particle]) -> Drift {
+        let mut energy_scale = 0.0;
+        let mut momentum_scale = 0.0;
+        let mut angular_momentum_scale = 0.0;
+        for (i, particle) in particles.iter().enumerate() {
+            let speed = particle.velocity.norm();
+            energy_scale += 0.5 * particle.gm * speed.powi(2);
+            for other in &particles[i + 1..] {
+                energy_scale +=
+                    particle.gm * other.gm / (particle.position - other.position).norm();
+            }
+            momentum_scale += particle.gm * speed;
+            angular_momentum_scale += particle.gm * particle.position.norm() * speed;
+        }
+        Drift {
+            energy: relative((self.energy - initial.energy).abs(), energy_scale),
+            momentum: relative((self.momentum - initial.momentum).norm(), momentum_scale),
+            angular_momentum: relative(
+                (self.angular_momentum - initial.angular_momentum).norm(),
+                angular_momentum_scale,
+            ),
+        }
+    }
+}
+
+/* A change as a fraction of scale, or as is when there's nothing to scale
+ * it by */
+fn relative(change: f64, scale: f64) -> f64 {
+    if scale > 0.0 && scale.is_finite() {
+        change / scale
+    } else {
+        change
+    }
+}
+
+/// Relative change in the conserved quantities since the start
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Drift {
+    pub energy: f64,
+    pub momentum: f64,
+    pub angular_momentum: f64,
+}
+
+impl Drift {
+    /// Largest of each drift between this and another
+    pub fn max(self, other: Drift) -> Drift {
+        Drift {
+            energy: self.energy.max(other.energy),
+            momentum: self.momentum.max(other.momentum),
+            angular_momentum: self.angular_momentum.max(other.angular_momentum),
+        }
+    }
+}
+
+impl std::fmt::Display for Drift {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "\
+             Energy Drift:           {:.10e}\n\
+             Momentum Drift:         {:.10e}\n\
+             Angular Momentum Drift: {:.10e}",
+            self.energy, self.momentum, self.angular_momentum
+        )
+    }
+}
+
+/// A set of bodies all attracting each other
+#[derive(Debug, Clone, PartialEq)]
+pub struct NBody {
+    pub particles: Vec<Particle>,
+    pub settings: IntegratorSettings,
+}
+
+impl NBody {
+    pub fn new(particles: Vec<Particle>) -> NBody {
+        NBody {
+            particles,
+            settings: IntegratorSettings::default(),
+        }
+    }
+
+    /// Shift every body into the barycentric frame, so the system as a
+    /// whole stays put instead of drifting off with the Sun's velocity
+    pub fn barycentric(mut self) -> NBody {
+        let total: f64 = self.particles.iter().map(|particle| particle.gm).sum();
+        let (position, velocity) = self.particles.iter().fold(
+            (Vector3::zeros(), Vector3::zeros()),
+            |(position, velocity), particle| {
+                (
+                    position + particle.gm * particle.position,
+                    velocity + particle.gm * particle.velocity,
+                )
+            },
+        );
+        for particle in &mut self.particles {
+            particle.position -= position / total;
+            particle.velocity -= velocity / total;
+        }
+        self
+    }
+
+    pub fn invariants(&self) -> Invariants {
+        Invariants::new(&self.particles)
+    }
+
+    /// Integrate the system for duration days, which can be negative
+    pub fn integrate(&self, duration: f64) -> Result<NBodySolution, OrbitError> {
+        if self.particles.is_empty() || self.particles.iter().any(|particle| particle.gm < 0.0) {
+            return Err(OrbitError::DegenerateState);
+        }
+        let gms: Vec<f64> = self.particles.iter().map(|particle| particle.gm).collect();
+        let state = DVector::from_iterator(
+            6 * self.particles.len(),
+            self.particles.iter().flat_map(|particle| {
+                particle
+                    .position
+                    .iter()
+                    .chain(particle.velocity.iter())
+                    .cloned()
+                    .collect::<Vec<_>>()
+            }),
+        );
+        let solution = integrate(
+            |_, state| derivative(&gms, state),
+            0.0,
+            state,
+            duration,
+            &self.settings,
+        )?;
+        Ok(NBodySolution {
+            solution,
+            template: self.particles.clone(),
+            initial: self.invariants(),
+        })
+    }
+}
+
+/* Velocities and mutual accelerations of every body, six elements each */
+fn derivative(gms: &[f64], state: &DVector<f64>) -> DVector<f64> {
+    let position = |i: usize| Vector3::new(state[6 * i], state[6 * i + 1], state[6 * i + 2]);
+    let mut rate = DVector::zeros(state.len());
+    for i in 0..gms.len() {
+        for axis in 0..3 {
+            rate[6 * i + axis] = state[6 * i + 3 + axis];
+        }
+    }
+    // Each pair once, with equal and opposite pulls
+    for i in 0..gms.len() {
+        for j in i + 1..gms.len() {
+            let separation = position(j) - position(i);
+            let pull = separation / separation.norm().powi(3);
+            for axis in 0..3 {
+                rate[6 * i + 3 + axis] += gms[j] * pull[axis];
+                rate[6 * j + 3 + axis] -= gms[i] * pull[axis];
+            }
+        }
+    }
+    rate
+}
+
+/// Integrated N-body system
+#[derive(Debug, Clone)]
+pub struct NBodySolution {
+    pub solution: Solution,
+    /* Names and gms for the states that come out */
+    template: Vec<Particle>,
+    initial: Invariants,
+}
+
+impl NBodySolution {
+    /// Every body at a time, None outside of the integration
+    pub fn particles_at(&self, time: f64) -> Option<Vec<Particle>> {
+        let state = self.solution.state_at(time)?;
+        Some(
+            self.template
+                .iter()
+                .enumerate()
+                .map(|(i, particle)| Particle {
+                    position: Vector3::new(state[6 * i], state[6 * i + 1], state[6 * i + 2]),
+                    velocity: Vector3::new(state[6 * i + 3], state[6 * i + 4], state[6 * i + 5]),
+                    ..particle.clone()
+                })
+                .collect(),
+        )
+    }
+
+    /// Trajectory of the body at index relative to the body at primary,
+    /// with mu the sum of their gms
+    pub fn trajectory<I>(
+        &self,
+        index: usize,
+        primary: usize,
+        times: I,
+    ) -> Result<Trajectory, OrbitError>
+    where
+        I: IntoIterator<Item = f64>,
+    {
+        if index >= self.template.len() || primary >= self.template.len() {
+            return Err(OrbitError::DegenerateState);
+        }
+        let states = times
+            .into_iter()
+            .map(|time| {
+                let particles = self.particles_at(time).ok_or(OrbitError::DegenerateState)?;
+                Ok(State {
+                    time,
+                    position: particles[index].position - particles[primary].position,
+                    velocity: particles[index].velocity - particles[primary].velocity,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Trajectory {
+            mu: self.template[index].gm + self.template[primary].gm,
+            states,
+        })
+    }
+
+    /// Drift of the conserved quantities at a time
+    pub fn drift_at(&self, time: f64) -> Option<Drift> {
+        let particles = self.particles_at(time)?;
+        Some(Invariants::new(&particles).drift_from(&self.initial, &particles))
+    }
+
+    /// Worst drift at the end of any step of the integration
+    pub fn max_drift(&self) -> Drift {
+        let none = Drift {
+            energy: 0.0,
+            momentum: 0.0,
+            angular_momentum: 0.0,
+        };
+        self.solution
+            .times()
+            .filter_map(|time| self.drift_at(time))
+            .fold(none, Drift::max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{EARTHGM, JUPITERGM, SOLARGM};
+    use crate::integrator::Method;
+
+    fn sun() -> Particle {
+        Particle::new("Sun", SOLARGM, Vector3::zeros(), Vector3::zeros())
+    }
+
+    fn circular(name: &str, gm: f64, radius: f64) -> Particle {
+        let speed = ((SOLARGM + gm) / radius).sqrt();
+        Particle::new(
+            name,
+            gm,
+            Vector3::new(radius, 0.0, 0.0),
+            Vector3::new(0.0, speed * 0.9, speed * 0.1),
+        )
+    }
+
+    #[test]
+    fn two_bodies_match_kepler() {
+        let mut system = NBody::new(vec![sun(), circular("Jupiter", JUPITERGM, 5.2)]).barycentric();
+        system.settings = IntegratorSettings {
+            tolerance: 1e-12,
+            absolute_tolerance: 1e-15,
+            ..IntegratorSettings::new(Method::Rk87)
+        };
+        let relative = system.particles[1].relative_to(&system.particles[0]);
+        let duration = 1.5 * relative.orbital_period();
+        let solution = system.integrate(duration).unwrap();
+
+        let times: Vec<f64> = (0..=30)
+            .map(|step| duration * f64::from(step) / 30.0)
+            .collect();
+        let trajectory = solution.trajectory(1, 0, times).unwrap();
+        assert_eq!(trajectory.mu, SOLARGM + JUPITERGM);
+        for state in &trajectory {
+            let expected = relative.position_at_time(state.time).unwrap();
+            assert!((state.position - expected).norm() < 1e-9 * expected.norm());
+        }
+    }
+
+    #[test]
+    fn energy_drift_stays_bounded() {
+        let system = NBody::new(vec![
+            sun(),
+            circular("Earth", EARTHGM, 1.0),
+            circular("Jupiter", JUPITERGM, 5.2),
+        ])
+        .barycentric();
+        let solution = system.integrate(20.0 * 365.25).unwrap();
+        let drift = solution.max_drift();
+        assert!(drift.energy < 1e-8, "{}", drift);
+        assert!(drift.momentum < 1e-12, "{}", drift);
+        assert!(drift.angular_momentum < 1e-8, "{}", drift);
+    }
+
+    #[test]
+    fn drift_is_finite_with_zero_invariants() {
+        // Two equal bodies flying straight apart at exactly escape speed:
+        // no energy, no momentum and no angular momentum
+        let particles = vec![
+            Particle::new(
+                "A",
+                1.0,
+                Vector3::new(-0.5, 0.0, 0.0),
+                Vector3::new(-1.0, 0.0, 0.0),
+            ),
+            Particle::new(
+                "B",
+                1.0,
+                Vector3::new(0.5, 0.0, 0.0),
+                Vector3::new(1.0, 0.0, 0.0),
+            ),
+        ];
+        let initial = Invariants::new(&particles);
+        assert_eq!(initial.energy, 0.0);
+        assert_eq!(initial.angular_momentum, Vector3::zeros());
+        let drift = initial.drift_from(&initial, &particles);
+        assert_eq!(
+            (drift.energy, drift.momentum, drift.angular_momentum),
+            (0.0, 0.0, 0.0)
+        );
+
+        let solution = NBody::new(particles).integrate(5.0).unwrap();
+        let drift = solution.max_drift();
+        assert!(drift.energy.is_finite() && drift.energy < 1e-8, "{}", drift);
+        assert!(drift.angular_momentum.is_finite(), "{}", drift);
+    }
+}
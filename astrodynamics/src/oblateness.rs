@@ -0,0 +1,256 @@
+/*!
+ * Oblateness of the central body through its zonal harmonics J2, J3 and
+ * J4. Everything here assumes the state is in the central body's equator
+ * frame, with z along its rotation pole; the heliocentric ecliptic frame
+ * the planetary states use is not that frame.
+ *
+ * There are two ways to use it. Oblateness is a ForceModel giving the full
+ * perturbing acceleration for the numerical integrator. For quick analytic
+ * work, Body::secular_at_time moves a body along its two-body orbit but
+ * with the node, argument of periapsis and mean anomaly drifting at their
+ * first order J2 secular rates (Vallado, section 9.6).
+ */
+use nalgebra::{Rotation3, Unit, Vector3};
+
+use super::body::Body;
+use super::constants::{EARTHEQUATORIALRADIUS, EARTHJ2, EARTHJ3, EARTHJ4};
+use super::error::OrbitError;
+use super::force::ForceModel;
+use super::planet::Planet;
+use super::units::Length;
+
+/// Zonal gravity field of a central body. Set j3 or j4 to zero to leave
+/// them out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Oblateness {
+    /// Gravitational parameter, AU^3/day^2
+    pub gm: f64,
+    /// Equatorial radius the harmonics are referenced to, AU
+    pub radius: f64,
+    pub j2: f64,
+    pub j3: f64,
+    pub j4: f64,
+}
+
+impl Oblateness {
+    /// J2 only field for a planet. radius is the equatorial radius j2 is
+    /// referenced to, which usually isn't the planet's mean radius.
+    pub fn new(planet: &Planet, radius: Length, j2: f64) -> Oblateness {
+        Oblateness {
+            gm: planet.gm,
+            radius: radius.to_au(),
+            j2,
+            j3: 0.0,
+            j4: 0.0,
+        }
+    }
+
+    /// Earth with J2 through J4
+    pub fn earth() -> Oblateness {
+        Oblateness {
+            j3: EARTHJ3,
+            j4: EARTHJ4,
+            ..Oblateness::new(&Planet::earth(), Length::km(EARTHEQUATORIALRADIUS), EARTHJ2)
+        }
+    }
+
+    /// First order J2 secular rates for a closed orbit about this body
+    pub fn secular_rates(&self, body: &Body) -> Result<SecularRates, OrbitError> {
+        let e = body.eccentricity();
+        if e >= 1.0 {
+            return Err(OrbitError::OpenOrbit);
+        }
+        let a = body.semi_major_axis();
+        let n = (body.mu / a.powi(3)).sqrt();
+        let p = a * (1.0 - e.powi(2));
+        let factor = n * self.j2 * (self.radius / p).powi(2);
+        let cos_i = body.inclination().cos();
+        Ok(SecularRates {
+            node: -1.5 * factor * cos_i,
+            periapsis: 0.75 * factor * (5.0 * cos_i.powi(2) - 1.0),
+            mean_motion: n + 0.75 * factor * (1.0 - e.powi(2)).sqrt() * (3.0 * cos_i.powi(2) - 1.0),
+        })
+    }
+}
+
+impl ForceModel for Oblateness {
+    fn acceleration(&self, _: f64, position: &Vector3<f64>, _: &Vector3<f64>) -> Vector3<f64> {
+        let r = position.norm();
+        let (x, y, z) = (position[0], position[1], position[2]);
+        let s = (z / r).powi(2);
+        let mut acceleration = Vector3::zeros();
+
+        if self.j2 != 0.0 {
+            let k = -1.5 * self.j2 * self.gm * self.radius.powi(2) / r.powi(5);
+            acceleration += k * Vector3::new(
+                x * (1.0 - 5.0 * s),
+                y * (1.0 - 5.0 * s),
+                z * (3.0 - 5.0 * s),
+            );
+        }
+        if self.j3 != 0.0 {
+            let k = -2.5 * self.j3 * self.gm * self.radius.powi(3) / r.powi(7);
+            let planar = 3.0 * z - 7.0 * z * s;
+            acceleration += k * Vector3::new(
+                x * planar,
+                y * planar,
+                r.powi(2) * (6.0 * s - 7.0 * s.powi(2) - 0.6),
+            );
+        }
+        if self.j4 != 0.0 {
+            let k = 1.875 * self.j4 * self.gm * self.radius.powi(4) / r.powi(7);
+            let planar = 1.0 - 14.0 * s + 21.0 * s.powi(2);
+            acceleration += k * Vector3::new(
+                x * planar,
+                y * planar,
+                z * (5.0 - 70.0 / 3.0 * s + 21.0 * s.powi(2)),
+            );
+        }
+        acceleration
+    }
+}
+
+/// Secular rates of the node, argument of periapsis and mean anomaly,
+/// rad/day. mean_motion includes the two-body mean motion.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SecularRates {
+    pub node: f64,
+    pub periapsis: f64,
+    pub mean_motion: f64,
+}
+
+impl Body {
+    /// State after time days on the two-body orbit, with the node,
+    /// argument of periapsis and mean anomaly drifting at their J2
+    /// secular rates. Short period terms are left out, and the current
+    /// osculating elements stand in for mean elements, so expect the
+    /// position along the orbit to wander off from a numerical solution
+    /// much sooner than the node does.
+    pub fn secular_at_time(&self, field: &Oblateness, time: f64) -> Result<Body, OrbitError> {
+        let rates = field.secular_rates(self)?;
+        let n = (self.mu / self.semi_major_axis().powi(3)).sqrt();
+
+        // Going along the unperturbed orbit for a scaled time gets the
+        // perturbed mean anomaly, then turning the orbit in its plane and
+        // about the pole moves the periapsis and node. Doing it with
+        // rotations keeps circular and equatorial orbits, which don't have
+        // a well defined periapsis or node, out of trouble.
+        let moved = self.at_time(time * rates.mean_motion / n)?;
+        let in_plane = Rotation3::from_axis_angle(
+            &Unit::new_normalize(self.angular_momentum()),
+            rates.periapsis * time,
+        );
+        let about_pole = Rotation3::from_axis_angle(&Vector3::z_axis(), rates.node * time);
+        let turn = about_pole * in_plane;
+        Ok(Body::new(
+            turn * moved.position,
+            turn * moved.velocity,
+            self.mu,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elements::KeplerianElements;
+    use crate::force::TwoBody;
+    use crate::integrator::{unpack, IntegratorSettings, Method};
+    use std::f64::consts::PI;
+
+    fn orbit(field: &Oblateness, a_km: f64, e: f64, inclination: f64) -> Body {
+        let elements = KeplerianElements::from_degrees(
+            Length::km(a_km).to_au(),
+            e,
+            inclination,
+            30.0,
+            40.0,
+            0.0,
+        );
+        Body::from_elements(&elements, field.gm).unwrap()
+    }
+
+    /* Difference between two angles, wrapped into [-pi, pi) */
+    fn angle_between(to: f64, from: f64) -> f64 {
+        (to - from + PI).rem_euclid(2.0 * PI) - PI
+    }
+
+    #[test]
+    fn secular_rates_match_integration() {
+        let field = Oblateness {
+            j3: 0.0,
+            j4: 0.0,
+            ..Oblateness::earth()
+        };
+        let body = orbit(&field, 9000.0, 0.1, 50.0);
+        let rates = field.secular_rates(&body).unwrap();
+
+        // Whole anomalistic periods, so the short period terms come back
+        // around to where they started
+        let period = 2.0 * PI / rates.mean_motion;
+        let duration = 60.0 * period;
+        let settings = IntegratorSettings {
+            tolerance: 1e-11,
+            absolute_tolerance: 1e-16,
+            ..IntegratorSettings::new(Method::Rk87)
+        };
+        let solution = body
+            .integrate(&(TwoBody::new(field.gm), field), duration, &settings)
+            .unwrap();
+        let (position, velocity) = unpack(&solution.state_at(duration).unwrap());
+        let end = Body::new(position, velocity, field.gm);
+
+        let node_rate = angle_between(
+            end.argument_of_ascending_node(),
+            body.argument_of_ascending_node(),
+        ) / duration;
+        let periapsis_rate =
+            angle_between(end.argument_of_periapsis(), body.argument_of_periapsis()) / duration;
+        assert!(node_rate < 0.0);
+        assert!(
+            (node_rate - rates.node).abs() < 0.01 * rates.node.abs(),
+            "{} vs {}",
+            node_rate,
+            rates.node
+        );
+        assert!(
+            (periapsis_rate - rates.periapsis).abs() < 0.02 * rates.periapsis.abs(),
+            "{} vs {}",
+            periapsis_rate,
+            rates.periapsis
+        );
+
+        // The analytic propagation turns the node at the same rate
+        let secular = body.secular_at_time(&field, duration).unwrap();
+        let secular_rate = angle_between(
+            secular.argument_of_ascending_node(),
+            body.argument_of_ascending_node(),
+        ) / duration;
+        assert!((secular_rate - rates.node).abs() < 1e-9 * rates.node.abs());
+    }
+
+    #[test]
+    fn sun_synchronous_node_rate() {
+        // 800 km up at 98.6 degrees the node keeps pace with the Sun
+        let field = Oblateness::earth();
+        let body = orbit(&field, EARTHEQUATORIALRADIUS + 800.0, 0.0, 98.6);
+        let rate = field.secular_rates(&body).unwrap().node.to_degrees();
+        let sun_rate = 360.0 / 365.2422;
+        assert!((rate - sun_rate).abs() < 0.01 * sun_rate, "{}", rate);
+    }
+
+    #[test]
+    fn secular_rates_need_a_closed_orbit() {
+        let field = Oblateness::earth();
+        let escape = (2.0 * field.gm / field.radius).sqrt();
+        let body = Body::new(
+            Vector3::new(2.0 * field.radius, 0.0, 0.0),
+            Vector3::new(0.0, escape, 0.0),
+            field.gm,
+        );
+        assert_eq!(
+            field.secular_rates(&body).unwrap_err(),
+            OrbitError::OpenOrbit
+        );
+    }
+}
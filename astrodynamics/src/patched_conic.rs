@@ -0,0 +1,375 @@
+/*!
+ * Patched-conic propagation. A trajectory follows a two-body conic about
+ * the central body until it enters a planet's sphere of influence, then
+ * gets re-expressed relative to the planet and follows a conic about it
+ * until it leaves again, and so on. Each of those pieces is a Leg.
+ *
+ * The planets move along their own two-body orbits about the central body.
+ * Sphere of influence radii come from the Laplace formula with each
+ * planet's current distance, r_soi = r (gm / mu)^(2/5).
+ *
+ * Crossings are found by stepping and then bisecting on the distance to
+ * the sphere. The step is never bigger than the time it would take to
+ * close half the gap to the nearest sphere at the current relative speed,
+ * so a fast pass can't be stepped over.
+ */
+use nalgebra::Vector3;
+
+use super::body::Body;
+use super::error::OrbitError;
+use super::planet::Planet;
+
+/* Crossing times are refined until they're this close, days */
+const TOLERANCE: f64 = 1e-9;
+const MAX_ITERATIONS: usize = 100;
+
+/* Smallest step taken while looking for a crossing, days */
+const MIN_STEP: f64 = 1e-4;
+
+/// A planet and its orbit about the central body at time zero
+#[derive(Debug, Clone)]
+pub struct SoiBody {
+    pub planet: Planet,
+    pub orbit: Body,
+}
+
+impl SoiBody {
+    pub fn new(planet: Planet, orbit: Body) -> SoiBody {
+        SoiBody { planet, orbit }
+    }
+
+    /// Sphere of influence radius (AU) at a time
+    pub fn sphere_of_influence(&self, time: f64) -> Result<f64, OrbitError> {
+        let position = self.orbit.position_at_time(time)?;
+        Ok(self
+            .planet
+            .sphere_of_influence(position.norm(), self.orbit.mu))
+    }
+}
+
+impl Planet {
+    /// Laplace sphere of influence radius at a distance from a primary
+    /// with gravitational parameter mu, same units as distance
+    pub fn sphere_of_influence(&self, distance: f64, mu: f64) -> f64 {
+        distance * (self.gm / mu).powf(0.4)
+    }
+}
+
+/// Body a leg is relative to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Primary {
+    Central,
+    /// Index into PatchedConic::planets
+    Planet(usize),
+}
+
+/// One conic of a patched-conic trajectory. state is relative to the
+/// primary at the start time; times are in days from the start.
+#[derive(Debug, Clone)]
+pub struct Leg {
+    pub primary: Primary,
+    pub start: f64,
+    pub end: f64,
+    pub state: Body,
+}
+
+impl Leg {
+    /// State relative to the leg's primary at a time in the leg
+    pub fn state_at(&self, time: f64) -> Result<Body, OrbitError> {
+        self.state.at_time(time - self.start)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PatchedConic {
+    pub central_mu: f64,
+    pub planets: Vec<SoiBody>,
+    /// Largest step taken while looking for a crossing, days
+    pub max_step: f64,
+}
+
+impl PatchedConic {
+    pub fn new(central_mu: f64, planets: Vec<SoiBody>) -> PatchedConic {
+        PatchedConic {
+            central_mu,
+            planets,
+            max_step: 10.0,
+        }
+    }
+
+    /// Propagate a body, given relative to the central body at time zero,
+    /// for duration days and return every leg along the way
+    pub fn propagate(&self, body: &Body, duration: f64) -> Result<Vec<Leg>, OrbitError> {
+        let mut primary = Primary::Central;
+        let mut state = Body::new(body.position, body.velocity, self.central_mu);
+
+        // Starting inside a sphere of influence means starting on that leg
+        for (index, soi) in self.planets.iter().enumerate() {
+            let planet = soi.orbit.at_time(0.0)?;
+            if (body.position - planet.position).norm() < soi.sphere_of_influence(0.0)? {
+                primary = Primary::Planet(index);
+                state = Body::new(
+                    body.position - planet.position,
+                    body.velocity - planet.velocity,
+                    soi.planet.gm,
+                );
+                break;
+            }
+        }
+
+        let mut legs = Vec::new();
+        let mut start = 0.0;
+        loop {
+            let leg = Leg {
+                primary,
+                start,
+                end: duration,
+                state: state.clone(),
+            };
+            let crossing = self.next_crossing(&leg, duration)?;
+            match crossing {
+                None => {
+                    legs.push(leg);
+                    return Ok(legs);
+                }
+                Some((time, next)) => {
+                    let (position, velocity) = self.heliocentric(&leg, time)?;
+                    state = match next {
+                        Primary::Central => Body::new(position, velocity, self.central_mu),
+                        Primary::Planet(index) => {
+                            let soi = &self.planets[index];
+                            let planet = soi.orbit.at_time(time)?;
+                            Body::new(
+                                position - planet.position,
+                                velocity - planet.velocity,
+                                soi.planet.gm,
+                            )
+                        }
+                    };
+                    legs.push(Leg { end: time, ..leg });
+                    primary = next;
+                    start = time;
+                }
+            }
+        }
+    }
+
+    /// State relative to the central body at a time, from a set of legs
+    /// made by propagate
+    pub fn state_at(&self, legs: &[Leg], time: f64) -> Result<Body, OrbitError> {
+        let leg = legs
+            .iter()
+            .find(|leg| time <= leg.end)
+            .or_else(|| legs.last())
+            .ok_or(OrbitError::DegenerateState)?;
+        let (position, velocity) = self.heliocentric(leg, time)?;
+        Ok(Body::new(position, velocity, self.central_mu))
+    }
+
+    /* Position and velocity relative to the central body at a time in a leg */
+    fn heliocentric(
+        &self,
+        leg: &Leg,
+        time: f64,
+    ) -> Result<(Vector3<f64>, Vector3<f64>), OrbitError> {
+        let state = leg.state_at(time)?;
+        match leg.primary {
+            Primary::Central => Ok((state.position, state.velocity)),
+            Primary::Planet(index) => {
+                let planet = self.planets[index].orbit.at_time(time)?;
+                Ok((
+                    state.position + planet.position,
+                    state.velocity + planet.velocity,
+                ))
+            }
+        }
+    }
+
+    /* Time of the first sphere of influence crossing on a leg before
+     * duration, and the primary on the other side of it */
+    fn next_crossing(
+        &self,
+        leg: &Leg,
+        duration: f64,
+    ) -> Result<Option<(f64, Primary)>, OrbitError> {
+        if let Primary::Planet(index) = leg.primary {
+            check_impact(&leg.state, &self.planets[index].planet)?;
+        }
+        let mut time = leg.start;
+        let mut gaps = self.gaps(leg, time)?;
+        while time < duration {
+            let step = gaps
+                .iter()
+                .map(|&(gap, speed)| 0.5 * gap.abs() / speed)
+                .fold(self.max_step, f64::min)
+                .max(MIN_STEP);
+            let next_time = (time + step).min(duration);
+            let next_gaps = self.gaps(leg, next_time)?;
+            for (index, (&(before, _), &(after, _))) in gaps.iter().zip(&next_gaps).enumerate() {
+                if before > 0.0 && after <= 0.0 {
+                    let crossing = self.bisect(leg, index, time, next_time)?;
+                    let next = match leg.primary {
+                        Primary::Central => Primary::Planet(index),
+                        Primary::Planet(_) => Primary::Central,
+                    };
+                    return Ok(Some((crossing, next)));
+                }
+            }
+            time = next_time;
+            gaps = next_gaps;
+        }
+        Ok(None)
+    }
+
+    /* How far the leg is from each boundary it could cross, positive while
+     * it hasn't crossed, along with the relative speed. On a central leg
+     * that's every planet's sphere from outside, on a planet leg it's only
+     * that planet's sphere from inside. */
+    fn gaps(&self, leg: &Leg, time: f64) -> Result<Vec<(f64, f64)>, OrbitError> {
+        let state = leg.state_at(time)?;
+        match leg.primary {
+            Primary::Central => self
+                .planets
+                .iter()
+                .map(|soi| {
+                    let planet = soi.orbit.at_time(time)?;
+                    let distance = (state.position - planet.position).norm();
+                    let speed = (state.velocity - planet.velocity).norm();
+                    Ok((distance - soi.sphere_of_influence(time)?, speed))
+                })
+                .collect(),
+            Primary::Planet(index) => {
+                let radius = self.planets[index].sphere_of_influence(time)?;
+                let gap = radius - state.position.norm();
+                // Only this planet's sphere matters, the others never cross
+                Ok(self
+                    .planets
+                    .iter()
+                    .enumerate()
+                    .map(|(other, _)| {
+                        if other == index {
+                            (gap, state.velocity.norm())
+                        } else {
+                            (f64::INFINITY, 1.0)
+                        }
+                    })
+                    .collect())
+            }
+        }
+    }
+
+    /* Narrow down when a boundary gets crossed between lower and upper */
+    fn bisect(
+        &self,
+        leg: &Leg,
+        index: usize,
+        mut lower: f64,
+        mut upper: f64,
+    ) -> Result<f64, OrbitError> {
+        for _ in 0..MAX_ITERATIONS {
+            if upper - lower < TOLERANCE {
+                break;
+            }
+            let middle = (upper + lower) / 2.0;
+            if self.gaps(leg, middle)?[index].0 > 0.0 {
+                lower = middle;
+            } else {
+                upper = middle;
+            }
+        }
+        Ok(upper)
+    }
+}
+
+/* A planet-centered conic coming in with its periapsis under the surface
+ * hits the planet before it can leave */
+fn check_impact(state: &Body, planet: &Planet) -> Result<(), OrbitError> {
+    let periapsis =
+        state.angular_momentum().norm_squared() / (state.mu * (1.0 + state.eccentricity()));
+    if periapsis < planet.radius && state.position.dot(&state.velocity) < 0.0 {
+        return Err(OrbitError::Impact);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::SOLARGM;
+    use crate::units::{Length, Speed};
+
+    fn earth_orbit() -> SoiBody {
+        let speed = SOLARGM.sqrt();
+        let orbit = Body::new(Vector3::x(), Vector3::new(0.0, speed, 0.0), SOLARGM);
+        SoiBody::new(Planet::earth(), orbit)
+    }
+
+    /* Spacecraft three sphere of influence radii ahead of Earth along its
+     * orbit, coming back at it at 3 km/s and aimed offset km to the side */
+    fn approach(soi: &SoiBody, offset: f64) -> Body {
+        let radius = soi.sphere_of_influence(0.0).unwrap();
+        let along = soi.orbit.velocity.normalize();
+        let side = soi.orbit.position.normalize();
+        let position =
+            soi.orbit.position + 3.0 * radius * along + Length::km(offset).to_au() * side;
+        let velocity = soi.orbit.velocity - Speed::km_per_s(3.0).to_au_per_day() * along;
+        Body::new(position, velocity, SOLARGM)
+    }
+
+    #[test]
+    fn finds_sphere_of_influence_crossings() {
+        let soi = earth_orbit();
+        let spacecraft = approach(&soi, 50_000.0);
+        let patched = PatchedConic::new(SOLARGM, vec![soi.clone()]);
+        let legs = patched.propagate(&spacecraft, 40.0).unwrap();
+        let primaries: Vec<Primary> = legs.iter().map(|leg| leg.primary).collect();
+        assert_eq!(
+            primaries,
+            vec![Primary::Central, Primary::Planet(0), Primary::Central]
+        );
+
+        // Both ends of the planet leg sit on the sphere, as closely as
+        // crossing times good to TOLERANCE days allow
+        for &time in &[legs[1].start, legs[1].end] {
+            let earth = soi.orbit.at_time(time).unwrap();
+            let state = patched.state_at(&legs, time).unwrap();
+            let distance = (state.position - earth.position).norm();
+            let speed = (state.velocity - earth.velocity).norm();
+            let radius = soi.sphere_of_influence(time).unwrap();
+            assert!((distance - radius).abs() < 2.0 * speed * TOLERANCE);
+        }
+        assert_eq!(legs[0].end, legs[1].start);
+        assert_eq!(legs[2].end, 40.0);
+    }
+
+    #[test]
+    fn impact_below_the_surface() {
+        let soi = earth_orbit();
+        let patched = PatchedConic::new(SOLARGM, vec![soi.clone()]);
+        assert_eq!(
+            patched.propagate(&approach(&soi, 0.0), 40.0).unwrap_err(),
+            OrbitError::Impact
+        );
+
+        // Periapsis 100 km under the surface, inbound and then outbound
+        let planet = Planet::earth();
+        let periapsis = planet.radius - Length::km(100.0).to_au();
+        let speed = 1.2 * (2.0 * planet.gm / periapsis).sqrt();
+        let at_periapsis = Body::new(
+            Vector3::new(periapsis, 0.0, 0.0),
+            Vector3::new(0.0, speed, 0.0),
+            planet.gm,
+        );
+        let inbound = at_periapsis.at_time(-0.1).unwrap();
+        let outbound = at_periapsis.at_time(0.1).unwrap();
+        assert_eq!(check_impact(&inbound, &planet), Err(OrbitError::Impact));
+        assert_eq!(check_impact(&outbound, &planet), Ok(()));
+
+        let above = Body::new(
+            Vector3::new(planet.radius * 1.1, 0.0, 0.0),
+            Vector3::new(0.0, speed, 0.0),
+            planet.gm,
+        );
+        assert_eq!(check_impact(&above.at_time(-0.1).unwrap(), &planet), Ok(()));
+    }
+}
@@ -0,0 +1,314 @@
+/*!
+ * Changing the orbit plane to a target inclination and argument of the
+ * ascending node. A plane change can only happen where the orbit crosses
+ * the target plane, which is one of the two ends of the line where the
+ * planes intersect; turning the velocity there costs 2 v sin(angle / 2)
+ * using only the transverse part of the velocity, so the slower node is
+ * the cheaper one.
+ *
+ * combined_transfer also changes the orbit size, going to a circular orbit
+ * of a given radius in the target plane with a Hohmann transfer along the
+ * line of nodes. The plane change gets split between the two burns; all of
+ * it at the far burn is the usual combined maneuver at apoapsis, but some
+ * of it at the first burn is nearly always a little cheaper.
+ */
+use nalgebra::{Rotation3, Unit, Vector3};
+use std::f64::consts::PI;
+
+use super::body::Body;
+use super::error::OrbitError;
+use super::transfer::{transfer_orbit, Burn};
+
+/* Angle between the planes, radians, under which they're the same plane */
+const SAME_PLANE: f64 = 1e-12;
+
+/* Golden section search on the split */
+const TOLERANCE: f64 = 1e-10;
+const MAX_ITERATIONS: usize = 100;
+const GOLDEN: f64 = 0.618_033_988_749_894_9;
+
+/// Burns for a plane change, with burn times in days from now and the
+/// state right after each burn. split is the fraction of the plane change
+/// made at the first burn.
+#[derive(Debug, Clone)]
+pub struct PlaneChange {
+    pub burns: Vec<Burn>,
+    pub states: Vec<Body>,
+    pub split: f64,
+}
+
+impl PlaneChange {
+    pub fn total_delta_v(&self) -> f64 {
+        self.burns.iter().map(Burn::magnitude).sum()
+    }
+}
+
+/// Unit angular momentum of the plane with an inclination and argument of
+/// the ascending node, both in radians
+pub fn plane_normal(inclination: f64, arg_of_an: f64) -> Vector3<f64> {
+    Vector3::new(
+        inclination.sin() * arg_of_an.sin(),
+        -inclination.sin() * arg_of_an.cos(),
+        inclination.cos(),
+    )
+}
+
+/// Single burn that rotates the orbit into the target plane, made at
+/// whichever node is cheaper. The orbit's size and shape don't change.
+pub fn plane_change(
+    body: &Body,
+    inclination: f64,
+    arg_of_an: f64,
+) -> Result<PlaneChange, OrbitError> {
+    let (rotation, node) = match plane_rotation(body, inclination, arg_of_an) {
+        Some(found) => found,
+        None => {
+            return Ok(PlaneChange {
+                burns: Vec::new(),
+                states: Vec::new(),
+                split: 1.0,
+            })
+        }
+    };
+
+    let mut best: Option<PlaneChange> = None;
+    for direction in &[node, -node] {
+        let time = body.time_until_crossing(*direction)?;
+        let before = body.at_time(time)?;
+        let after = Body::new(before.position, rotation * before.velocity, body.mu);
+        let change = PlaneChange {
+            burns: vec![Burn {
+                time,
+                delta_v: after.velocity - before.velocity,
+            }],
+            states: vec![after],
+            split: 1.0,
+        };
+        best = match best {
+            Some(best) if best.total_delta_v() <= change.total_delta_v() => Some(best),
+            _ => Some(change),
+        };
+    }
+    best.ok_or(OrbitError::NoSolution)
+}
+
+/// Hohmann transfer along the line of nodes to a circular orbit of radius
+/// in the target plane, with the plane change split between both burns to
+/// keep the total delta-v as small as possible. Both nodes are tried as the
+/// departure point.
+pub fn combined_transfer(
+    body: &Body,
+    radius: f64,
+    inclination: f64,
+    arg_of_an: f64,
+) -> Result<PlaneChange, OrbitError> {
+    let target = plane_normal(inclination, arg_of_an);
+    let (rotation, node) = plane_rotation(body, inclination, arg_of_an)
+        .unwrap_or_else(|| (Rotation3::identity(), body.position.normalize()));
+    let (axis, angle) = match rotation.axis_angle() {
+        Some((axis, angle)) => (axis, angle),
+        None => (Unit::new_normalize(node), 0.0),
+    };
+
+    let mut best: Option<PlaneChange> = None;
+    for direction in &[node, -node] {
+        let wait = body.time_until_crossing(*direction)?;
+        let start = body.at_time(wait)?;
+        let leg = transfer_orbit(&start, radius);
+        let leg_time = PI * (leg.semi_major_axis().powi(3) / leg.mu).sqrt();
+        let arrival = leg.at_time(leg_time)?;
+        let circular = (body.mu / radius).sqrt() * target.cross(&arrival.position).normalize();
+
+        // Delta-v for both burns when split of the plane change happens at
+        // the first one. The rotation is about the line of nodes, so the
+        // whole transfer orbit turns with it.
+        let burns = |split: f64| {
+            let turn = Rotation3::from_axis_angle(&axis, split * angle);
+            (
+                turn * leg.velocity - start.velocity,
+                circular - turn * arrival.velocity,
+            )
+        };
+        let cost = |split: f64| {
+            let (first, second) = burns(split);
+            first.norm() + second.norm()
+        };
+        let split = minimize(cost);
+        let (first, second) = burns(split);
+        let turn = Rotation3::from_axis_angle(&axis, split * angle);
+
+        let change = PlaneChange {
+            burns: vec![
+                Burn {
+                    time: wait,
+                    delta_v: first,
+                },
+                Burn {
+                    time: wait + leg_time,
+                    delta_v: second,
+                },
+            ],
+            states: vec![
+                Body::new(leg.position, turn * leg.velocity, body.mu),
+                Body::new(arrival.position, circular, body.mu),
+            ],
+            split,
+        };
+        best = match best {
+            Some(best) if best.total_delta_v() <= change.total_delta_v() => Some(best),
+            _ => Some(change),
+        };
+    }
+    best.ok_or(OrbitError::NoSolution)
+}
+
+/* Rotation about the line of nodes that takes the orbit plane into the
+ * target plane, and the ascending node direction of the target plane
+ * relative to the current one. None if the planes are already the same. */
+fn plane_rotation(
+    body: &Body,
+    inclination: f64,
+    arg_of_an: f64,
+) -> Option<(Rotation3<f64>, Vector3<f64>)> {
+    let current = body.angular_momentum().normalize();
+    let target = plane_normal(inclination, arg_of_an);
+    let node = current.cross(&target);
+    if node.norm() < SAME_PLANE {
+        if current.dot(&target) > 0.0 {
+            return None;
+        }
+        // Flipping the orbit over, any line in the plane works
+        let axis = Unit::new_normalize(body.position);
+        return Some((Rotation3::from_axis_angle(&axis, PI), axis.into_inner()));
+    }
+    let angle = node.norm().atan2(current.dot(&target));
+    let axis = Unit::new_normalize(node);
+    Some((Rotation3::from_axis_angle(&axis, angle), axis.into_inner()))
+}
+
+/* Golden section search for the split in [0, 1] with the lowest cost */
+fn minimize<F: Fn(f64) -> f64>(cost: F) -> f64 {
+    let (mut lower, mut upper) = (0.0, 1.0);
+    let mut left = upper - GOLDEN * (upper - lower);
+    let mut right = lower + GOLDEN * (upper - lower);
+    let (mut c_left, mut c_right) = (cost(left), cost(right));
+    for _ in 0..MAX_ITERATIONS {
+        if upper - lower < TOLERANCE {
+            break;
+        }
+        if c_left < c_right {
+            upper = right;
+            right = left;
+            c_right = c_left;
+            left = upper - GOLDEN * (upper - lower);
+            c_left = cost(left);
+        } else {
+            lower = left;
+            left = right;
+            c_left = c_right;
+            right = lower + GOLDEN * (upper - lower);
+            c_right = cost(right);
+        }
+    }
+    // The ends can't be reached from inside, so check them too
+    let split = (upper + lower) / 2.0;
+    [0.0, split, 1.0]
+        .iter()
+        .cloned()
+        .fold(split, |best, s| if cost(s) < cost(best) { s } else { best })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::EARTHGM;
+    use crate::elements::KeplerianElements;
+    use crate::units::Length;
+
+    fn orbit(radius_km: f64, eccentricity: f64, inclination: f64) -> Body {
+        let elements = KeplerianElements::from_degrees(
+            Length::km(radius_km).to_au(),
+            eccentricity,
+            inclination,
+            0.0,
+            30.0,
+            50.0,
+        );
+        Body::from_elements(&elements, EARTHGM).unwrap()
+    }
+
+    fn assert_plane(body: &Body, inclination: f64, arg_of_an: f64) {
+        let normal = body.angular_momentum().normalize();
+        assert!((normal - plane_normal(inclination, arg_of_an)).norm() < 1e-9);
+    }
+
+    #[test]
+    fn circular_plane_change_costs_two_v_sin() {
+        let body = orbit(7000.0, 0.0, 28.5);
+        let (inclination, node) = (38.5_f64.to_radians(), 30.0_f64.to_radians());
+        let change = plane_change(&body, inclination, node).unwrap();
+        assert_eq!(change.burns.len(), 1);
+        let speed = body.velocity.norm();
+        let expected = 2.0 * speed * (10.0_f64.to_radians() / 2.0).sin();
+        assert!((change.total_delta_v() - expected).abs() < 1e-9 * expected);
+        assert_plane(&change.states[0], inclination, node);
+        assert!((change.states[0].velocity.norm() - speed).abs() < 1e-12 * speed);
+    }
+
+    #[test]
+    fn elliptic_plane_change_goes_at_apoapsis() {
+        // Periapsis on the node, so the far node is apoapsis where the
+        // velocity is all transverse and slowest
+        let body = orbit(12000.0, 0.4, 10.0);
+        let (inclination, node) = (25.0_f64.to_radians(), 30.0_f64.to_radians());
+        let change = plane_change(&body, inclination, node).unwrap();
+        let a = body.semi_major_axis();
+        let apoapsis_speed = (body.mu / a * (1.0 - 0.4) / (1.0 + 0.4)).sqrt();
+        let expected = 2.0 * apoapsis_speed * (15.0_f64.to_radians() / 2.0).sin();
+        assert!((change.total_delta_v() - expected).abs() < 1e-9 * expected);
+        assert!((change.states[0].position.norm() - a * 1.4).abs() < 1e-9 * a);
+        assert_plane(&change.states[0], inclination, node);
+    }
+
+    #[test]
+    fn same_plane_needs_no_burn() {
+        let body = orbit(7000.0, 0.1, 28.5);
+        let change = plane_change(&body, 28.5_f64.to_radians(), 30.0_f64.to_radians()).unwrap();
+        assert!(change.burns.is_empty());
+    }
+
+    #[test]
+    fn split_beats_either_end() {
+        let body = orbit(7000.0, 0.0, 28.5);
+        let (r_1, r_2) = (body.position.norm(), Length::km(42164.0).to_au());
+        let (inclination, node) = (0.5_f64.to_radians(), 30.0_f64.to_radians());
+        let change = combined_transfer(&body, r_2, inclination, node).unwrap();
+
+        // Closed form for a circular to circular transfer with split of the
+        // 28 degree plane change made at the first burn
+        let mu = body.mu;
+        let a = (r_1 + r_2) / 2.0;
+        let (v_1, v_2) = ((mu / r_1).sqrt(), (mu / r_2).sqrt());
+        let v_p = (mu * (2.0 / r_1 - 1.0 / a)).sqrt();
+        let v_a = (mu * (2.0 / r_2 - 1.0 / a)).sqrt();
+        let angle = 28.0_f64.to_radians();
+        let burn =
+            |v: f64, w: f64, turn: f64| (v.powi(2) + w.powi(2) - 2.0 * v * w * turn.cos()).sqrt();
+        let cost =
+            |split: f64| burn(v_1, v_p, split * angle) + burn(v_a, v_2, (1.0 - split) * angle);
+
+        assert!(change.split > 0.0 && change.split < 0.2);
+        assert!((change.total_delta_v() - cost(change.split)).abs() < 1e-9 * cost(0.0));
+        assert!(change.total_delta_v() < cost(0.0));
+        assert!(change.total_delta_v() < cost(1.0));
+        let best = (0..=1000)
+            .map(|step| cost(f64::from(step) / 1000.0))
+            .fold(f64::INFINITY, f64::min);
+        assert!(change.total_delta_v() <= best + 1e-12);
+
+        let arrival = change.states.last().unwrap();
+        assert!((arrival.position.norm() - r_2).abs() < 1e-9 * r_2);
+        assert!(arrival.eccentricity() < 1e-9);
+        assert_plane(arrival, inclination, node);
+    }
+}
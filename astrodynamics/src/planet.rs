@@ -0,0 +1,81 @@
+/*!
+ * Physical properties of a planet (or any other body something can fly
+ * past or orbit), as opposed to Body, which is a state on an orbit. Same
+ * idea as the orbitable Body in hw1: gm, radius and length of day, with
+ * the values from hw1's table built in for the planets.
+ */
+use super::units::{GravParam, Length, Time};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Planet {
+    pub name: String,
+    /// Gravitational parameter, AU^3/day^2
+    pub gm: f64,
+    /// Mean radius, AU. Fine for altitudes, shadows and impacts, but not
+    /// the reference radius a gravity field's coefficients go with.
+    pub radius: f64,
+    /// Sidereal rotation period in days, negative for retrograde rotation
+    pub day_len: f64,
+}
+
+impl Planet {
+    pub fn new(name: &str, gm: GravParam, radius: Length, day_len: Time) -> Planet {
+        Planet {
+            name: String::from(name),
+            gm: gm.to_au3_per_day2(),
+            radius: radius.to_au(),
+            day_len: day_len.to_days(),
+        }
+    }
+
+    /* Shorthand for the hw1 table, which is in km^3/s^2, km and days */
+    fn from_table(name: &str, gm: f64, radius: f64, day_len: f64) -> Planet {
+        Planet::new(
+            name,
+            GravParam::km3_per_s2(gm),
+            Length::km(radius),
+            Time::days(day_len),
+        )
+    }
+
+    pub fn mercury() -> Planet {
+        Planet::from_table("Mercury", 22032.09, 2440.0, 58.6462)
+    }
+
+    pub fn venus() -> Planet {
+        Planet::from_table("Venus", 324858.63, 6051.8, -243.0185)
+    }
+
+    pub fn earth() -> Planet {
+        Planet::from_table("Earth", 398600.440, 6371.01, 0.997257916)
+    }
+
+    pub fn mars() -> Planet {
+        Planet::from_table("Mars", 42828.3, 3389.9, 1.0274907)
+    }
+
+    pub fn jupiter() -> Planet {
+        Planet::from_table("Jupiter", 1.26686511e8, 69911.0, 0.413538)
+    }
+
+    pub fn saturn() -> Planet {
+        Planet::from_table("Saturn", 3.79312078e7, 58232.0, 0.4440083)
+    }
+
+    pub fn uranus() -> Planet {
+        Planet::from_table("Uranus", 5.793966e6, 25362.0, 0.7183)
+    }
+
+    pub fn neptune() -> Planet {
+        Planet::from_table("Neptune", 6.835107e6, 24624.0, 0.67125)
+    }
+
+    pub fn pluto() -> Planet {
+        Planet::from_table("Pluto", 872.4, 1195.0, 5.342128)
+    }
+
+    /// Rotation rate about the pole, rad/day
+    pub fn rotation_rate(&self) -> f64 {
+        2.0 * std::f64::consts::PI / self.day_len
+    }
+}
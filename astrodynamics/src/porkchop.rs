@@ -0,0 +1,577 @@
+/*!
+ * Porkchop plots. Lambert gets solved for every pair of departure and
+ * arrival dates on a grid, and each transfer is scored by its launch
+ * energy C3, the hyperbolic excess speeds at both ends and their sum.
+ * The grid can be written out as CSV or as SVG contours.
+ *
+ * Everything here is reported in km/s and km^2/s^2 since that's how
+ * porkchop plots are always read; the dates are Julian, so the date! macro
+ * can be used to build the ranges.
+ */
+use std::io::{self, Write};
+
+use super::body::Body;
+use super::error::OrbitError;
+use super::lambert::{lambert, Direction};
+use super::units::{AUTOKM, DAYTOSEC};
+
+/* Relative difference in mu past which two ephemerides aren't about the
+ * same central body */
+const MU_TOLERANCE: f64 = 1e-12;
+
+/* SVG layout, in pixels */
+const PLOT_SIZE: f64 = 600.0;
+const MARGIN: f64 = 80.0;
+
+/// Julian dates from start to end, step days apart. end is included when
+/// the step lands on it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DateRange {
+    pub start: f64,
+    pub end: f64,
+    pub step: f64,
+}
+
+impl DateRange {
+    pub fn new(start: f64, end: f64, step: f64) -> DateRange {
+        DateRange { start, end, step }
+    }
+
+    pub fn dates(&self) -> Vec<f64> {
+        if self.step <= 0.0 || self.end < self.start {
+            return vec![self.start];
+        }
+        let count = ((self.end - self.start) / self.step + 1e-9).floor() as usize;
+        (0..=count)
+            .map(|i| self.start + i as f64 * self.step)
+            .collect()
+    }
+}
+
+/// A body's state along with the Julian date it's valid at
+#[derive(Debug, Clone)]
+pub struct Ephemeris {
+    pub body: Body,
+    pub epoch: f64,
+}
+
+impl Ephemeris {
+    pub fn new(body: Body, epoch: f64) -> Ephemeris {
+        Ephemeris { body, epoch }
+    }
+}
+
+/// One transfer on the grid
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transfer {
+    pub departure: f64,
+    pub arrival: f64,
+    /// Launch energy, km^2/s^2
+    pub c3: f64,
+    /// Hyperbolic excess speed leaving the departure body, km/s
+    pub v_inf_departure: f64,
+    /// Hyperbolic excess speed arriving at the target, km/s
+    pub v_inf_arrival: f64,
+    /// v-inf at departure plus v-inf at arrival, km/s. Not a delta-v: the
+    /// burns to leave a parking orbit or capture at the target depend on
+    /// those orbits and aren't in it.
+    pub v_inf_sum: f64,
+}
+
+/// Quantity to draw contours of
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Quantity {
+    C3,
+    VInfDeparture,
+    VInfArrival,
+    /// Sum of the two excess speeds
+    TotalVInf,
+}
+
+impl Quantity {
+    fn of(self, transfer: &Transfer) -> f64 {
+        match self {
+            Quantity::C3 => transfer.c3,
+            Quantity::VInfDeparture => transfer.v_inf_departure,
+            Quantity::VInfArrival => transfer.v_inf_arrival,
+            Quantity::TotalVInf => transfer.v_inf_sum,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Quantity::C3 => "C3 (km^2/s^2)",
+            Quantity::VInfDeparture => "Departure v-inf (km/s)",
+            Quantity::VInfArrival => "Arrival v-inf (km/s)",
+            Quantity::TotalVInf => "Departure + arrival v-inf (km/s)",
+        }
+    }
+}
+
+/// Transfers for every departure/arrival pair. Pairs that have no transfer
+/// (arriving before leaving, or Lambert failing) are None.
+#[derive(Debug, Clone)]
+pub struct Porkchop {
+    pub departures: Vec<f64>,
+    pub arrivals: Vec<f64>,
+    /* departures.len() * arrivals.len(), departure major */
+    transfers: Vec<Option<Transfer>>,
+}
+
+impl Porkchop {
+    /// Solve Lambert over the grid for a prograde transfer from one body to
+    /// the other. Both bodies need to be about the same central body, or
+    /// this returns CentralBodyMismatch.
+    pub fn new(
+        from: &Ephemeris,
+        to: &Ephemeris,
+        departures: &DateRange,
+        arrivals: &DateRange,
+    ) -> Result<Porkchop, OrbitError> {
+        let (mu, to_mu) = (from.body.mu, to.body.mu);
+        if (mu - to_mu).abs() > MU_TOLERANCE * mu.abs().max(to_mu.abs()) {
+            return Err(OrbitError::CentralBodyMismatch);
+        }
+        let departures = departures.dates();
+        let arrivals = arrivals.dates();
+        let from_path = from
+            .body
+            .propagate(departures.iter().map(|date| date - from.epoch))?;
+        let to_path = to
+            .body
+            .propagate(arrivals.iter().map(|date| date - to.epoch))?;
+        let h_hat = from.body.angular_momentum().normalize();
+        let speed = AUTOKM / DAYTOSEC;
+
+        let mut transfers = Vec::with_capacity(departures.len() * arrivals.len());
+        for (departure, leave) in departures.iter().zip(&from_path) {
+            for (arrival, reach) in arrivals.iter().zip(&to_path) {
+                let time_of_flight = arrival - departure;
+                if time_of_flight <= 0.0 {
+                    transfers.push(None);
+                    continue;
+                }
+                // Go the way that keeps the transfer moving with the
+                // departure body
+                let direction = if leave.position.cross(&reach.position).dot(&h_hat) >= 0.0 {
+                    Direction::Short
+                } else {
+                    Direction::Long
+                };
+                let solution = lambert(
+                    leave.position,
+                    reach.position,
+                    time_of_flight,
+                    mu,
+                    direction,
+                );
+                transfers.push(solution.ok().map(|solution| {
+                    let v_inf_departure =
+                        (solution.departure.velocity - leave.velocity).norm() * speed;
+                    let v_inf_arrival = (solution.arrival.velocity - reach.velocity).norm() * speed;
+                    Transfer {
+                        departure: *departure,
+                        arrival: *arrival,
+                        c3: v_inf_departure.powi(2),
+                        v_inf_departure,
+                        v_inf_arrival,
+                        v_inf_sum: v_inf_departure + v_inf_arrival,
+                    }
+                }));
+            }
+        }
+
+        Ok(Porkchop {
+            departures,
+            arrivals,
+            transfers,
+        })
+    }
+
+    /// Transfer for the i-th departure date and j-th arrival date
+    pub fn get(&self, i: usize, j: usize) -> Option<&Transfer> {
+        if i >= self.departures.len() || j >= self.arrivals.len() {
+            return None;
+        }
+        self.transfers[i * self.arrivals.len() + j].as_ref()
+    }
+
+    /// Every transfer that has a solution
+    pub fn transfers(&self) -> impl Iterator<Item = &Transfer> {
+        self.transfers
+            .iter()
+            .filter_map(|transfer| transfer.as_ref())
+    }
+
+    /// The transfer with the smallest value of a quantity
+    pub fn best(&self, quantity: Quantity) -> Option<&Transfer> {
+        self.transfers()
+            .fold(None, |best: Option<&Transfer>, transfer| match best {
+                Some(best) if quantity.of(best) <= quantity.of(transfer) => Some(best),
+                _ => Some(transfer),
+            })
+    }
+
+    /// One row per grid point, blank fields where there's no transfer
+    pub fn write_csv<W: Write>(&self, mut out: W) -> io::Result<()> {
+        writeln!(
+            out,
+            "departure_jd,arrival_jd,time_of_flight_days,c3_km2_s2,\
+             v_inf_departure_km_s,v_inf_arrival_km_s,v_inf_sum_km_s"
+        )?;
+        for (i, departure) in self.departures.iter().enumerate() {
+            for (j, arrival) in self.arrivals.iter().enumerate() {
+                write!(out, "{},{},{}", departure, arrival, arrival - departure)?;
+                match self.get(i, j) {
+                    Some(t) => writeln!(
+                        out,
+                        ",{},{},{},{}",
+                        t.c3, t.v_inf_departure, t.v_inf_arrival, t.v_inf_sum
+                    )?,
+                    None => writeln!(out, ",,,,")?,
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Contour plot of a quantity with departure date along the x axis and
+    /// arrival date up the y axis, one line per level
+    pub fn write_svg<W: Write>(
+        &self,
+        mut out: W,
+        quantity: Quantity,
+        levels: &[f64],
+    ) -> io::Result<()> {
+        let size = PLOT_SIZE + 2.0 * MARGIN;
+        writeln!(
+            out,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{0}\" \
+             font-family=\"sans-serif\" font-size=\"12\">",
+            size
+        )?;
+        writeln!(
+            out,
+            "<rect x=\"{0}\" y=\"{0}\" width=\"{1}\" height=\"{1}\" fill=\"none\" stroke=\"black\"/>",
+            MARGIN, PLOT_SIZE
+        )?;
+
+        // Axis labels with the first and last dates on each axis
+        let (x_first, x_last) = ends(&self.departures);
+        let (y_first, y_last) = ends(&self.arrivals);
+        let bottom = MARGIN + PLOT_SIZE;
+        writeln!(
+            out,
+            "<text x=\"{}\" y=\"{}\">{:.1}</text>",
+            MARGIN,
+            bottom + 20.0,
+            x_first
+        )?;
+        writeln!(
+            out,
+            "<text x=\"{}\" y=\"{}\" text-anchor=\"end\">{:.1}</text>",
+            bottom,
+            bottom + 20.0,
+            x_last
+        )?;
+        writeln!(
+            out,
+            "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\">Departure (JD)</text>",
+            size / 2.0,
+            bottom + 45.0
+        )?;
+        writeln!(
+            out,
+            "<text x=\"{}\" y=\"{}\" text-anchor=\"end\">{:.1}</text>",
+            MARGIN - 5.0,
+            bottom,
+            y_first
+        )?;
+        writeln!(
+            out,
+            "<text x=\"{}\" y=\"{}\" text-anchor=\"end\">{:.1}</text>",
+            MARGIN - 5.0,
+            MARGIN + 12.0,
+            y_last
+        )?;
+        writeln!(
+            out,
+            "<text transform=\"translate({} {}) rotate(-90)\" text-anchor=\"middle\">Arrival (JD)</text>",
+            20.0,
+            size / 2.0
+        )?;
+        writeln!(
+            out,
+            "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text>",
+            size / 2.0,
+            MARGIN / 2.0,
+            quantity.label()
+        )?;
+
+        // Grid coordinates to pixels, arrival dates increase going up
+        let x_scale = PLOT_SIZE / (x_last - x_first).max(f64::EPSILON);
+        let y_scale = PLOT_SIZE / (y_last - y_first).max(f64::EPSILON);
+        let pixel = |x: f64, y: f64| {
+            (
+                MARGIN + (x - x_first) * x_scale,
+                bottom - (y - y_first) * y_scale,
+            )
+        };
+
+        for (index, level) in levels.iter().enumerate() {
+            let hue = 240.0 * (1.0 - index as f64 / (levels.len().max(2) - 1) as f64);
+            write!(
+                out,
+                "<path fill=\"none\" stroke=\"hsl({:.0},80%,45%)\" d=\"",
+                hue
+            )?;
+            for ((x_0, y_0), (x_1, y_1)) in self.contour(quantity, *level) {
+                let (p_0, q_0) = pixel(x_0, y_0);
+                let (p_1, q_1) = pixel(x_1, y_1);
+                write!(out, "M{:.1} {:.1}L{:.1} {:.1}", p_0, q_0, p_1, q_1)?;
+            }
+            writeln!(out, "\"/>")?;
+            writeln!(
+                out,
+                "<text x=\"{}\" y=\"{}\" fill=\"hsl({:.0},80%,45%)\">{}</text>",
+                bottom + 10.0,
+                MARGIN + 15.0 * (index as f64 + 1.0),
+                hue,
+                level
+            )?;
+        }
+        writeln!(out, "</svg>")
+    }
+
+    /* Line segments, in (departure, arrival) dates, where the quantity
+     * crosses level. Marching squares over every cell of the grid that has
+     * transfers at all four corners. */
+    fn contour(&self, quantity: Quantity, level: f64) -> Vec<((f64, f64), (f64, f64))> {
+        let mut segments = Vec::new();
+        let value = |i: usize, j: usize| self.get(i, j).map(|t| quantity.of(t));
+        for i in 0..self.departures.len().saturating_sub(1) {
+            for j in 0..self.arrivals.len().saturating_sub(1) {
+                // Corners counter-clockwise from the bottom left
+                let corners = [(i, j), (i + 1, j), (i + 1, j + 1), (i, j + 1)];
+                let values: Option<Vec<f64>> = corners.iter().map(|&(a, b)| value(a, b)).collect();
+                let values = match values {
+                    Some(values) => values,
+                    None => continue,
+                };
+                let mut crossings = Vec::with_capacity(4);
+                for edge in 0..4 {
+                    let (v_0, v_1) = (values[edge], values[(edge + 1) % 4]);
+                    if (v_0 - level) * (v_1 - level) < 0.0 {
+                        let frac = (level - v_0) / (v_1 - v_0);
+                        let (a_0, b_0) = corners[edge];
+                        let (a_1, b_1) = corners[(edge + 1) % 4];
+                        let x_0 = self.departures[a_0];
+                        let y_0 = self.arrivals[b_0];
+                        crossings.push((
+                            x_0 + frac * (self.departures[a_1] - x_0),
+                            y_0 + frac * (self.arrivals[b_1] - y_0),
+                        ));
+                    }
+                }
+                for pair in crossings.chunks(2) {
+                    if pair.len() == 2 {
+                        segments.push((pair[0], pair[1]));
+                    }
+                }
+            }
+        }
+        segments
+    }
+}
+
+/* First and last values of a range of dates */
+fn ends(dates: &[f64]) -> (f64, f64) {
+    (
+        dates.first().cloned().unwrap_or(0.0),
+        dates.last().cloned().unwrap_or(0.0),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::SOLARGM;
+    use crate::elements::KeplerianElements;
+    use crate::kepler::{solve_elliptic, SolverSettings};
+
+    /* Heliocentric state from Standish's mean elements (a, e, I, L, long.
+     * of perihelion, node) and their rates per century, J2000 ecliptic */
+    fn mean_elements(elements: [f64; 6], rates: [f64; 6], date: f64) -> Body {
+        let centuries = (date - 2_451_545.0) / 36525.0;
+        let at = |index: usize| elements[index] + rates[index] * centuries;
+        let (eccen, perihelion, node) = (at(1), at(4), at(5));
+        let e_anom = solve_elliptic(
+            (at(3) - perihelion).to_radians(),
+            eccen,
+            &SolverSettings::default(),
+        )
+        .into_result()
+        .unwrap();
+        let t_anom = 2.0
+            * ((1.0 + eccen).sqrt() * (e_anom / 2.0).sin())
+                .atan2((1.0 - eccen).sqrt() * (e_anom / 2.0).cos());
+        let elements = KeplerianElements::from_degrees(
+            at(0),
+            eccen,
+            at(2),
+            perihelion - node,
+            node,
+            t_anom.to_degrees(),
+        );
+        Body::from_elements(&elements, SOLARGM).unwrap()
+    }
+
+    fn earth(date: f64) -> Ephemeris {
+        let body = mean_elements(
+            [
+                1.00000261,
+                0.01671123,
+                -0.00001531,
+                100.46457166,
+                102.93768193,
+                0.0,
+            ],
+            [
+                0.00000562,
+                -0.00004392,
+                -0.01294668,
+                35999.37244981,
+                0.32327364,
+                0.0,
+            ],
+            date,
+        );
+        Ephemeris::new(body, date)
+    }
+
+    fn mars(date: f64) -> Ephemeris {
+        let body = mean_elements(
+            [
+                1.52371034,
+                0.09339410,
+                1.84969142,
+                -4.55343205,
+                -23.94362959,
+                49.55953891,
+            ],
+            [
+                0.00001847,
+                0.00007882,
+                -0.00813131,
+                19140.30268499,
+                0.44441088,
+                -0.29257343,
+            ],
+            date,
+        );
+        Ephemeris::new(body, date)
+    }
+
+    /* Porkchop from a made up field over a small grid, value(x, y) at each
+     * date pair */
+    fn synthetic<F: Fn(f64, f64) -> Option<f64>>(size: usize, value: F) -> Porkchop {
+        let dates: Vec<f64> = (0..size).map(|i| i as f64).collect();
+        let mut transfers = Vec::new();
+        for &departure in &dates {
+            for &arrival in &dates {
+                transfers.push(value(departure, arrival).map(|c3| Transfer {
+                    departure,
+                    arrival,
+                    c3,
+                    v_inf_departure: c3.sqrt(),
+                    v_inf_arrival: 0.0,
+                    v_inf_sum: c3.sqrt(),
+                }));
+            }
+        }
+        Porkchop {
+            departures: dates.clone(),
+            arrivals: dates,
+            transfers,
+        }
+    }
+
+    #[test]
+    fn earth_mars_2005_minimum_c3() {
+        // The 2005 type I opportunity, which Mars Reconnaissance Orbiter
+        // launched on in August, bottoms out a little under 16 km^2/s^2
+        let epoch = date!(2005-6-1 00:00:00);
+        let porkchop = Porkchop::new(
+            &earth(epoch),
+            &mars(epoch),
+            &DateRange::new(date!(2005-7-15 00:00:00), date!(2005-9-15 00:00:00), 2.0),
+            &DateRange::new(date!(2006-1-1 00:00:00), date!(2006-4-30 00:00:00), 2.0),
+        )
+        .unwrap();
+        let best = porkchop.best(Quantity::C3).unwrap();
+        assert!(best.c3 > 15.0 && best.c3 < 17.0, "C3 {}", best.c3);
+        assert!(best.departure >= date!(2005-8-1 00:00:00));
+        assert!(best.departure <= date!(2005-8-15 00:00:00));
+        let time_of_flight = best.arrival - best.departure;
+        assert!(time_of_flight > 180.0 && time_of_flight < 215.0);
+        assert!((best.v_inf_sum - best.v_inf_departure - best.v_inf_arrival).abs() < 1e-12);
+        assert!((best.c3 - best.v_inf_departure.powi(2)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn mismatched_central_bodies_are_an_error() {
+        let epoch = date!(2005-6-1 00:00:00);
+        let mut moon = mars(epoch);
+        moon.body = Body::new(moon.body.position, moon.body.velocity, SOLARGM * 1.1);
+        let dates = DateRange::new(epoch, epoch + 10.0, 5.0);
+        assert_eq!(
+            Porkchop::new(&earth(epoch), &moon, &dates, &dates).unwrap_err(),
+            OrbitError::CentralBodyMismatch
+        );
+    }
+
+    #[test]
+    fn csv_has_a_row_per_grid_point() {
+        // Arrivals before departures have no transfer
+        let porkchop = synthetic(4, |x, y| if y > x { Some(x + y) } else { None });
+        let mut csv = Vec::new();
+        porkchop.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "departure_jd,arrival_jd,time_of_flight_days,c3_km2_s2,\
+             v_inf_departure_km_s,v_inf_arrival_km_s,v_inf_sum_km_s"
+        );
+        assert_eq!(lines.len(), 1 + 16);
+        assert!(lines.iter().all(|line| line.split(',').count() == 7));
+        assert_eq!(lines[1], "0,0,0,,,,");
+        assert_eq!(lines[2], "0,1,1,1,1,0,1");
+    }
+
+    #[test]
+    fn contours_follow_a_plane() {
+        // x + y = 3.5 crosses the grid diagonally
+        let porkchop = synthetic(5, |x, y| Some(x + y));
+        let segments = porkchop.contour(Quantity::C3, 3.5);
+        // One segment in each cell the line passes through
+        assert_eq!(segments.len(), 7);
+        for ((x_0, y_0), (x_1, y_1)) in segments {
+            assert!((x_0 + y_0 - 3.5).abs() < 1e-12);
+            assert!((x_1 + y_1 - 3.5).abs() < 1e-12);
+        }
+        assert!(porkchop.contour(Quantity::C3, 20.0).is_empty());
+    }
+
+    #[test]
+    fn contours_skip_cells_without_transfers() {
+        let porkchop = synthetic(5, |x, y| if x == 2.0 { None } else { Some(x + y) });
+        let segments = porkchop.contour(Quantity::C3, 3.5);
+        // Of the seven cells the line crosses, only the three away from
+        // x = 2 are left
+        assert_eq!(segments.len(), 3);
+        assert!(segments
+            .iter()
+            .all(|&((x_0, _), (x_1, _))| x_0.max(x_1) <= 1.0 || x_0.min(x_1) >= 3.0));
+    }
+}
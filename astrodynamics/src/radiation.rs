@@ -0,0 +1,223 @@
+/*!
+ * Solar radiation pressure on a cannonball spacecraft: a sphere with an
+ * area to mass ratio and a reflectivity coefficient C_r, between 1 for
+ * a perfect absorber and 2 for a perfect mirror. The push is straight away
+ * from the Sun and falls off with the square of the distance from it,
+ *
+ *   a = nu P C_r (A / m) (1 AU / d)^2 d_hat
+ *
+ * with P the pressure at 1 AU and nu the fraction of the Sun that's
+ * visible. When the central body isn't the Sun, it can block the Sun and
+ * nu comes from a shadow model: a cylinder of the body's radius (all or
+ * nothing), or the cones of the umbra and penumbra, which also covers the
+ * partial eclipse on the way in and out (Montenbruck and Gill, 3.4.2).
+ */
+use nalgebra::Vector3;
+use std::f64::consts::PI;
+
+use super::body::Body;
+use super::force::ForceModel;
+use super::planet::Planet;
+use super::units::{AUTOKM, AUTOM, DAYTOSEC};
+
+/// Solar radiation pressure at 1 AU, N/m^2
+pub const SOLAR_PRESSURE: f64 = 4.56e-6;
+
+/// Sun's radius, km
+pub const SUN_RADIUS: f64 = 696_000.0;
+
+/// How the central body's shadow is worked out
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Shadow {
+    /// Never in shadow
+    None,
+    Cylindrical,
+    Conical,
+}
+
+impl Shadow {
+    /// Fraction of the Sun visible, from 0 in full shadow to 1 in full
+    /// sunlight. position and sun are relative to the shadowing body, which
+    /// has a radius of radius, all in AU.
+    pub fn fraction(self, position: &Vector3<f64>, sun: &Vector3<f64>, radius: f64) -> f64 {
+        match self {
+            Shadow::None => 1.0,
+            Shadow::Cylindrical => {
+                let sun_hat = sun.normalize();
+                let along = position.dot(&sun_hat);
+                if along < 0.0 && (position - along * sun_hat).norm() < radius {
+                    0.0
+                } else {
+                    1.0
+                }
+            }
+            Shadow::Conical => {
+                let to_sun = sun - position;
+                let sun_radius = SUN_RADIUS / AUTOKM;
+                // Apparent radii of the Sun and the body, and how far apart
+                // their centers look
+                let a = (sun_radius / to_sun.norm()).min(1.0).asin();
+                let b = (radius / position.norm()).min(1.0).asin();
+                let c = (-position.dot(&to_sun) / (position.norm() * to_sun.norm()))
+                    .clamp(-1.0, 1.0)
+                    .acos();
+                if c >= a + b {
+                    1.0
+                } else if c <= b - a {
+                    0.0
+                } else if c <= a - b {
+                    // Annular, the body sits inside the Sun's disk
+                    1.0 - (b / a).powi(2)
+                } else {
+                    let x = (c.powi(2) + a.powi(2) - b.powi(2)) / (2.0 * c);
+                    let y = (a.powi(2) - x.powi(2)).max(0.0).sqrt();
+                    let overlap = a.powi(2) * (x / a).clamp(-1.0, 1.0).acos()
+                        + b.powi(2) * ((c - x) / b).clamp(-1.0, 1.0).acos()
+                        - c * y;
+                    1.0 - overlap / (PI * a.powi(2))
+                }
+            }
+        }
+    }
+}
+
+/// Cannonball solar radiation pressure
+#[derive(Debug, Clone)]
+pub struct RadiationPressure {
+    /// m^2/kg
+    pub area_to_mass: f64,
+    pub reflectivity: f64,
+    /// The Sun's state relative to the central body at time zero, or None
+    /// if the central body is the Sun
+    pub sun: Option<Body>,
+    /// Radius of the central body for its shadow, AU
+    pub radius: f64,
+    pub shadow: Shadow,
+}
+
+impl RadiationPressure {
+    /// Radiation pressure on something orbiting the Sun itself
+    pub fn heliocentric(area_to_mass: f64, reflectivity: f64) -> RadiationPressure {
+        RadiationPressure {
+            area_to_mass,
+            reflectivity,
+            sun: None,
+            radius: 0.0,
+            shadow: Shadow::None,
+        }
+    }
+
+    /// Radiation pressure on something orbiting a planet, with the Sun's
+    /// state relative to the planet at time zero
+    pub fn planetocentric(
+        area_to_mass: f64,
+        reflectivity: f64,
+        planet: &Planet,
+        sun: Body,
+        shadow: Shadow,
+    ) -> RadiationPressure {
+        RadiationPressure {
+            area_to_mass,
+            reflectivity,
+            sun: Some(sun),
+            radius: planet.radius,
+            shadow,
+        }
+    }
+
+    /// Sun's position relative to the central body at a time
+    pub fn sun_position(&self, time: f64) -> Vector3<f64> {
+        match &self.sun {
+            // A Sun that can't be propagated just stays where it was
+            Some(sun) => sun.position_at_time(time).unwrap_or(sun.position),
+            None => Vector3::zeros(),
+        }
+    }
+}
+
+impl ForceModel for RadiationPressure {
+    fn acceleration(&self, time: f64, position: &Vector3<f64>, _: &Vector3<f64>) -> Vector3<f64> {
+        let sun = self.sun_position(time);
+        let fraction = self.shadow.fraction(position, &sun, self.radius);
+        if fraction == 0.0 {
+            return Vector3::zeros();
+        }
+        let away = position - sun;
+        // P C_r A/m is in m/s^2 at 1 AU
+        let magnitude = SOLAR_PRESSURE * self.reflectivity * self.area_to_mass * DAYTOSEC.powi(2)
+            / AUTOM
+            / away.norm_squared();
+        fraction * magnitude * away.normalize()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::units::Length;
+
+    fn earth_radius() -> f64 {
+        Planet::earth().radius
+    }
+
+    /* Sun along +x at 1 AU, a spacecraft at x behind or in front of Earth,
+     * y off the shadow's axis, both in Earth radii */
+    fn fraction(shadow: Shadow, x: f64, y: f64) -> f64 {
+        let radius = earth_radius();
+        let position = Vector3::new(x * radius, y * radius, 0.0);
+        shadow.fraction(&position, &Vector3::new(1.0, 0.0, 0.0), radius)
+    }
+
+    #[test]
+    fn cylindrical_shadow() {
+        assert_eq!(fraction(Shadow::Cylindrical, -1.1, 0.0), 0.0);
+        assert_eq!(fraction(Shadow::Cylindrical, -1000.0, 0.99), 0.0);
+        assert_eq!(fraction(Shadow::Cylindrical, -1.1, 1.01), 1.0);
+        assert_eq!(fraction(Shadow::Cylindrical, 1.1, 0.0), 1.0);
+        assert_eq!(fraction(Shadow::None, -1.1, 0.0), 1.0);
+    }
+
+    #[test]
+    fn conical_shadow() {
+        assert_eq!(fraction(Shadow::Conical, -1.1, 0.0), 0.0);
+        assert_eq!(fraction(Shadow::Conical, 1.1, 0.0), 1.0);
+        assert_eq!(fraction(Shadow::Conical, -1.1, 1.1), 1.0);
+
+        // With the Sun's center on Earth's limb, about half of it shows
+        let x = -6.0;
+        let half = fraction(Shadow::Conical, x, 1.0);
+        assert!((half - 0.5).abs() < 0.01, "{}", half);
+
+        // Through the penumbra the Sun comes back steadily
+        let mut last = 0.0;
+        for step in 0..=200 {
+            let visible = fraction(Shadow::Conical, x, 0.95 + step as f64 * 5e-4);
+            assert!(visible >= last && (0.0..=1.0).contains(&visible));
+            last = visible;
+        }
+        assert_eq!(last, 1.0);
+    }
+
+    #[test]
+    fn conical_shadow_annular() {
+        // Past the end of the umbra a small body covers a ring's worth of
+        // the Sun
+        let radius = Length::km(1000.0).to_au();
+        let position = Vector3::new(-0.01, 0.0, 0.0);
+        let visible = Shadow::Conical.fraction(&position, &Vector3::new(1.0, 0.0, 0.0), radius);
+        let a = (SUN_RADIUS / AUTOKM / 1.01).asin();
+        let b = (radius / 0.01).asin();
+        assert!((visible - (1.0 - (b / a).powi(2))).abs() < 1e-12);
+        assert!(visible > 0.0 && visible < 1.0);
+    }
+
+    #[test]
+    fn pressure_at_one_au() {
+        let srp = RadiationPressure::heliocentric(0.02, 1.5);
+        let position = Vector3::new(0.0, 1.0, 0.0);
+        let acceleration = srp.acceleration(0.0, &position, &Vector3::zeros());
+        // m/s^2 to AU/day^2
+        let expected = SOLAR_PRESSURE * 1.5 * 0.02 * DAYTOSEC.powi(2) / AUTOM;
+        assert!((acceleration - expected * position).norm() < 1e-12 * expected);
+    }
+}
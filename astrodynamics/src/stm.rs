@@ -0,0 +1,290 @@
+/*!
+ * State transition matrix: the 6x6 matrix of partial derivatives of the
+ * position and velocity at a later time with respect to the starting ones,
+ * so a small change in the starting state maps forward as
+ *
+ *   [dr; dv] = Phi(t) [dr_0; dv_0]
+ *
+ * For two-body motion it comes out in closed form from the universal
+ * variables (Battin, section 9.7), which holds for every conic type and
+ * any number of revolutions. Under a force model, the variational equations
+ * Phi' = A Phi, with A the partial derivatives of the state's rate, are
+ * integrated alongside the state itself.
+ */
+use nalgebra::{DVector, Matrix3, Matrix6, Vector3, U3};
+
+use super::body::Body;
+use super::error::OrbitError;
+use super::force::ForceModel;
+use super::integrator::{integrate, pack, unpack, IntegratorSettings, Solution};
+use super::universal::{c2_coeff, c3_coeff, c4_coeff, c5_coeff, UniversalPropagator};
+
+/// A state integrated along with its state transition matrix
+#[derive(Debug, Clone)]
+pub struct Variational {
+    pub mu: f64,
+    /// Position and velocity followed by the matrix, column by column
+    pub solution: Solution,
+}
+
+impl Variational {
+    /// State at a time, None outside of the integration
+    pub fn state_at(&self, time: f64) -> Option<Body> {
+        let state = self.solution.state_at(time)?;
+        let (position, velocity) = unpack(&state);
+        Some(Body::new(position, velocity, self.mu))
+    }
+
+    /// State transition matrix from the start to a time, None outside of
+    /// the integration
+    pub fn transition_matrix_at(&self, time: f64) -> Option<Matrix6<f64>> {
+        let state = self.solution.state_at(time)?;
+        Some(Matrix6::from_iterator(state.iter().skip(6).cloned()))
+    }
+}
+
+/* Put the four 3x3 blocks of a state transition matrix together */
+fn from_blocks(
+    top_left: &Matrix3<f64>,
+    top_right: &Matrix3<f64>,
+    bottom_left: &Matrix3<f64>,
+    bottom_right: &Matrix3<f64>,
+) -> Matrix6<f64> {
+    let mut matrix = Matrix6::zeros();
+    matrix.fixed_slice_mut::<U3, U3>(0, 0).copy_from(top_left);
+    matrix.fixed_slice_mut::<U3, U3>(0, 3).copy_from(top_right);
+    matrix
+        .fixed_slice_mut::<U3, U3>(3, 0)
+        .copy_from(bottom_left);
+    matrix
+        .fixed_slice_mut::<U3, U3>(3, 3)
+        .copy_from(bottom_right);
+    matrix
+}
+
+impl Body {
+    /// Two-body state transition matrix from now to time days from now,
+    /// which can be negative
+    pub fn transition_matrix(&self, time: f64) -> Result<Matrix6<f64>, OrbitError> {
+        let mu = self.mu;
+        let sqrt_mu = mu.sqrt();
+        let (r_0, v_0) = (self.position, self.velocity);
+        let propagator = UniversalPropagator::new(r_0, v_0, mu)?;
+        let chi = propagator.universal_anomaly(time)?;
+        let (r, v) = propagator.state_at(time)?;
+        let r_0_mag = r_0.norm();
+        let r_mag = r.norm();
+
+        // Battin's universal functions U_n = chi^n c_n(alpha chi^2)
+        let alpha = 2.0 / r_0_mag - v_0.norm_squared() / mu;
+        let psi = alpha * chi.powi(2);
+        let u_1 = chi * (1.0 - psi * c3_coeff(psi));
+        let u_2 = chi.powi(2) * c2_coeff(psi);
+        let u_4 = chi.powi(4) * c4_coeff(psi);
+        let u_5 = chi.powi(5) * c5_coeff(psi);
+
+        let f = 1.0 - u_2 / r_0_mag;
+        let g = time - chi.powi(3) * c3_coeff(psi) / sqrt_mu;
+        let f_dot = -sqrt_mu * u_1 / (r_mag * r_0_mag);
+        let g_dot = 1.0 - u_2 / r_mag;
+        // Carries the secular part, which grows with the number of
+        // revolutions
+        let c = (3.0 * u_5 - chi * u_4) / sqrt_mu - time * u_2;
+
+        let dr = r - r_0;
+        let dv = v - v_0;
+        let identity = Matrix3::identity();
+        let by_position = f * identity
+            + r_mag / mu * dv * dv.transpose()
+            + (r_0_mag * (1.0 - f) * r * r_0.transpose() + c * v * r_0.transpose())
+                / r_0_mag.powi(3);
+        let by_velocity = g * identity
+            + r_0_mag / mu * (1.0 - f) * (dr * v_0.transpose() - dv * r_0.transpose())
+            + c / mu * v * v_0.transpose();
+        let turn: Vector3<f64> = r * r.dot(&v) - v * r_mag.powi(2);
+        let rate_by_position = f_dot
+            * (identity - r * r.transpose() / r_mag.powi(2) + turn * dv.transpose() / (mu * r_mag))
+            - dv * r_0.transpose() / r_0_mag.powi(2)
+            - r * dv.transpose() / r_mag.powi(2)
+            - mu * c / (r_mag * r_0_mag).powi(3) * r * r_0.transpose();
+        let rate_by_velocity = g_dot * identity
+            + r_0_mag / mu * dv * dv.transpose()
+            + (r_0_mag * (1.0 - f) * r * r_0.transpose() - c * r * v_0.transpose()) / r_mag.powi(3);
+
+        Ok(from_blocks(
+            &by_position,
+            &by_velocity,
+            &rate_by_position,
+            &rate_by_velocity,
+        ))
+    }
+
+    /// Integrate the state and its transition matrix under a force model
+    /// for duration days, which can be negative. Like Body::integrate, the
+    /// model gives the whole acceleration.
+    pub fn integrate_variational<M: ForceModel>(
+        &self,
+        model: &M,
+        duration: f64,
+        settings: &IntegratorSettings,
+    ) -> Result<Variational, OrbitError> {
+        let mut initial = DVector::zeros(42);
+        initial
+            .rows_mut(0, 6)
+            .copy_from(&pack(&self.position, &self.velocity));
+        initial
+            .rows_mut(6, 36)
+            .copy_from_slice(Matrix6::<f64>::identity().as_slice());
+
+        let solution = integrate(
+            |time, state| {
+                let (position, velocity) = unpack(state);
+                let acceleration = model.acceleration(time, &position, &velocity);
+                let (by_position, by_velocity) = model.partials(time, &position, &velocity);
+                let rates = from_blocks(
+                    &Matrix3::zeros(),
+                    &Matrix3::identity(),
+                    &by_position,
+                    &by_velocity,
+                );
+                let transition = Matrix6::from_iterator(state.iter().skip(6).cloned());
+
+                let mut derivative = DVector::zeros(42);
+                derivative
+                    .rows_mut(0, 6)
+                    .copy_from(&pack(&velocity, &acceleration));
+                derivative
+                    .rows_mut(6, 36)
+                    .copy_from_slice((rates * transition).as_slice());
+                derivative
+            },
+            0.0,
+            initial,
+            duration,
+            settings,
+        )?;
+        Ok(Variational {
+            mu: self.mu,
+            solution,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::SOLARGM;
+    use crate::force::TwoBody;
+    use crate::integrator::Method;
+
+    /* Transition matrix by central differences of the two-body propagator,
+     * nudging each starting component by a millionth of its vector */
+    fn differenced(body: &Body, time: f64) -> Matrix6<f64> {
+        let start = pack(&body.position, &body.velocity);
+        let mut matrix = Matrix6::zeros();
+        for column in 0..6 {
+            let scale = if column < 3 {
+                body.position.norm()
+            } else {
+                body.velocity.norm()
+            };
+            let step = 1e-6 * scale;
+            let end = |sign: f64| {
+                let mut state = start.clone();
+                state[column] += sign * step;
+                let (r_0, v_0) = unpack(&state);
+                let (r, v) = UniversalPropagator::new(r_0, v_0, body.mu)
+                    .unwrap()
+                    .state_at(time)
+                    .unwrap();
+                pack(&r, &v)
+            };
+            let slope = (end(1.0) - end(-1.0)) / (2.0 * step);
+            matrix.column_mut(column).copy_from(&slope);
+        }
+        matrix
+    }
+
+    fn relative_difference(a: &Matrix6<f64>, b: &Matrix6<f64>) -> f64 {
+        (a - b).norm() / b.norm()
+    }
+
+    fn ellipse() -> Body {
+        Body::new(
+            Vector3::new(0.9, 0.1, 0.0),
+            Vector3::new(-0.004, 0.021, 0.001),
+            SOLARGM,
+        )
+    }
+
+    fn hyperbola() -> Body {
+        Body::new(
+            Vector3::new(1.0, 0.0, 0.1),
+            Vector3::new(0.0, 0.03, 0.002),
+            SOLARGM,
+        )
+    }
+
+    #[test]
+    fn analytic_matches_differences() {
+        let cases = [
+            (ellipse(), 200.0),
+            (ellipse(), 3000.0),
+            (ellipse(), -300.0),
+            (hyperbola(), 150.0),
+        ];
+        for (body, time) in cases.iter() {
+            let analytic = body.transition_matrix(*time).unwrap();
+            let difference = relative_difference(&analytic, &differenced(body, *time));
+            assert!(
+                difference < 1e-7,
+                "off by {:e} at {} days",
+                difference,
+                time
+            );
+            // Two-body flow preserves phase space volume
+            assert!((analytic.determinant() - 1.0).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn identity_at_zero_time() {
+        let analytic = ellipse().transition_matrix(0.0).unwrap();
+        assert!(relative_difference(&analytic, &Matrix6::identity()) < 1e-14);
+    }
+
+    #[test]
+    fn variational_matches_analytic() {
+        let settings = IntegratorSettings {
+            tolerance: 1e-12,
+            absolute_tolerance: 1e-15,
+            ..IntegratorSettings::new(Method::Rk87)
+        };
+        let cases = [
+            (ellipse(), 500.0),
+            (ellipse(), -300.0),
+            (hyperbola(), 150.0),
+        ];
+        for (body, duration) in cases.iter() {
+            let variational = body
+                .integrate_variational(&TwoBody::new(SOLARGM), *duration, &settings)
+                .unwrap();
+            for &fraction in &[0.37, 1.0] {
+                let time = fraction * duration;
+                let integrated = variational.transition_matrix_at(time).unwrap();
+                let analytic = body.transition_matrix(time).unwrap();
+                let difference = relative_difference(&integrated, &analytic);
+                assert!(
+                    difference < 1e-8,
+                    "off by {:e} at {} days",
+                    difference,
+                    time
+                );
+
+                let state = variational.state_at(time).unwrap();
+                let expected = body.position_at_time(time).unwrap();
+                assert!((state.position - expected).norm() / expected.norm() < 1e-9);
+            }
+        }
+    }
+}
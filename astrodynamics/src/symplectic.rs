@@ -0,0 +1,373 @@
+/*!
+ * Fixed step symplectic integrators for N-body systems. Runge-Kutta
+ * methods let the energy wander off a little more every orbit, which adds
+ * up over centuries; a symplectic method exactly solves a Hamiltonian
+ * that's only slightly off from the real one, so its energy error stays
+ * bounded however long it runs.
+ *
+ * All of them split the Hamiltonian into pieces that can be solved
+ * exactly and alternate between them:
+ *   - Leapfrog splits kinetic from potential energy, drifting every body in
+ *     a straight line and then kicking it with all of the mutual
+ *     accelerations. Second order.
+ *   - Yoshida4 and Yoshida6 chain several leapfrog steps of carefully
+ *     chosen lengths, some of them backward, to cancel the error terms up
+ *     to fourth and sixth order.
+ *   - WisdomHolman splits the Keplerian motion about the first body (the
+ *     Sun) from the interactions between the others, in democratic
+ *     heliocentric coordinates (Duncan, Levison and Lee, 1998). The drift is
+ *     an exact Kepler solve through UniversalPropagator, so the only error
+ *     left is of the order of the planet to Sun mass ratio, and steps can
+ *     be a sizable fraction of the innermost orbit.
+ *
+ * The system is moved to the barycentric frame when the integrator is set
+ * up. Steps are fixed, and advance_to only goes in whole steps.
+ */
+use nalgebra::Vector3;
+
+use super::error::OrbitError;
+use super::nbody::{Drift, Invariants, NBody, Particle};
+use super::universal::UniversalPropagator;
+
+/* Yoshida's fourth order weights, 1 / (2 - 2^(1/3)) on the outside */
+const YOSHIDA_4: [f64; 3] = [
+    1.351_207_191_959_657_6,
+    -1.702_414_383_919_315_3,
+    1.351_207_191_959_657_6,
+];
+
+/* Yoshida's sixth order weights (solution A) */
+const YOSHIDA_6: [f64; 7] = [
+    0.784_513_610_477_560,
+    0.235_573_213_359_357,
+    -1.177_679_984_178_87,
+    1.315_186_320_683_906,
+    -1.177_679_984_178_87,
+    0.235_573_213_359_357,
+    0.784_513_610_477_560,
+];
+
+/// Symplectic integration scheme
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Method {
+    Leapfrog,
+    Yoshida4,
+    Yoshida6,
+    WisdomHolman,
+}
+
+/// An N-body system being stepped forward with a symplectic method
+#[derive(Debug, Clone)]
+pub struct Symplectic {
+    pub method: Method,
+    /// Step size, days, negative to go backward
+    pub step: f64,
+    /// Days since the start
+    pub time: f64,
+    names: Vec<String>,
+    gms: Vec<f64>,
+    /* Barycentric positions and velocities */
+    positions: Vec<Vector3<f64>>,
+    velocities: Vec<Vector3<f64>>,
+    initial: Invariants,
+}
+
+impl Symplectic {
+    /// Set a system up for integration. Wisdom-Holman needs the first body
+    /// to be the one everything else orbits.
+    pub fn new(system: &NBody, method: Method, step: f64) -> Result<Symplectic, OrbitError> {
+        let particles = system.clone().barycentric().particles;
+        if particles.is_empty()
+            || step == 0.0
+            || !step.is_finite()
+            || particles.iter().any(|particle| particle.gm < 0.0)
+            || (method == Method::WisdomHolman && particles[0].gm <= 0.0)
+        {
+            return Err(OrbitError::DegenerateState);
+        }
+        Ok(Symplectic {
+            method,
+            step,
+            time: 0.0,
+            names: particles.iter().map(|p| p.name.clone()).collect(),
+            gms: particles.iter().map(|p| p.gm).collect(),
+            positions: particles.iter().map(|p| p.position).collect(),
+            velocities: particles.iter().map(|p| p.velocity).collect(),
+            initial: Invariants::new(&particles),
+        })
+    }
+
+    /// Take one step
+    pub fn advance(&mut self) -> Result<(), OrbitError> {
+        match self.method {
+            Method::Leapfrog => self.leapfrog(self.step),
+            Method::Yoshida4 => {
+                for weight in &YOSHIDA_4 {
+                    self.leapfrog(weight * self.step);
+                }
+            }
+            Method::Yoshida6 => {
+                for weight in &YOSHIDA_6 {
+                    self.leapfrog(weight * self.step);
+                }
+            }
+            Method::WisdomHolman => self.wisdom_holman(self.step)?,
+        }
+        self.time += self.step;
+        Ok(())
+    }
+
+    /// Take whole steps until the next one would go past time
+    pub fn advance_to(&mut self, time: f64) -> Result<(), OrbitError> {
+        // A little slack so round-off in the running time can't cost a step
+        let slack = 1e-9 * self.step.abs();
+        while (time - (self.time + self.step)) * self.step.signum() >= -slack {
+            self.advance()?;
+        }
+        Ok(())
+    }
+
+    /// Every body in the barycentric frame as of now
+    pub fn particles(&self) -> Vec<Particle> {
+        (0..self.gms.len())
+            .map(|i| {
+                Particle::new(
+                    &self.names[i],
+                    self.gms[i],
+                    self.positions[i],
+                    self.velocities[i],
+                )
+            })
+            .collect()
+    }
+
+    /// Drift of the conserved quantities since the start
+    pub fn drift(&self) -> Drift {
+        let particles = self.particles();
+        Invariants::new(&particles).drift_from(&self.initial, &particles)
+    }
+
+    /* Drift, kick, drift */
+    fn leapfrog(&mut self, step: f64) {
+        self.drift_positions(step / 2.0);
+        let accelerations = self.accelerations(0);
+        for (velocity, acceleration) in self.velocities.iter_mut().zip(accelerations) {
+            *velocity += step * acceleration;
+        }
+        self.drift_positions(step / 2.0);
+    }
+
+    fn drift_positions(&mut self, step: f64) {
+        for (position, velocity) in self.positions.iter_mut().zip(&self.velocities) {
+            *position += step * velocity;
+        }
+    }
+
+    /* Mutual accelerations between every body from first on, leaving the
+     * ones before it out entirely */
+    fn accelerations(&self, first: usize) -> Vec<Vector3<f64>> {
+        let mut accelerations = vec![Vector3::zeros(); self.gms.len()];
+        for i in first..self.gms.len() {
+            for j in i + 1..self.gms.len() {
+                let separation = self.positions[j] - self.positions[i];
+                let pull = separation / separation.norm().powi(3);
+                accelerations[i] += self.gms[j] * pull;
+                accelerations[j] -= self.gms[i] * pull;
+            }
+        }
+        accelerations
+    }
+
+    /* One Wisdom-Holman step in democratic heliocentric coordinates:
+     * heliocentric positions with barycentric velocities. Half a kick from
+     * the interactions, half a step of the Sun's share of the momentum,
+     * a Kepler drift about the Sun, then back out the same way. */
+    fn wisdom_holman(&mut self, step: f64) -> Result<(), OrbitError> {
+        let sun_gm = self.gms[0];
+        let sun = self.positions[0];
+        for position in &mut self.positions[1..] {
+            *position -= sun;
+        }
+
+        self.interaction_kick(step / 2.0);
+        self.sun_drift(step / 2.0);
+        for (position, velocity) in self.positions[1..]
+            .iter_mut()
+            .zip(&mut self.velocities[1..])
+        {
+            let (new_position, new_velocity) =
+                UniversalPropagator::new(*position, *velocity, sun_gm)?.state_at(step)?;
+            *position = new_position;
+            *velocity = new_velocity;
+        }
+        self.sun_drift(step / 2.0);
+        self.interaction_kick(step / 2.0);
+
+        // Back to barycentric, with the Sun wherever keeps the barycenter
+        // at the origin
+        let total: f64 = self.gms.iter().sum();
+        let sun = -self.gms[1..]
+            .iter()
+            .zip(&self.positions[1..])
+            .fold(Vector3::zeros(), |sum, (gm, position)| sum + *gm * position)
+            / total;
+        let momentum = self.gms[1..]
+            .iter()
+            .zip(&self.velocities[1..])
+            .fold(Vector3::zeros(), |sum, (gm, velocity)| sum + *gm * velocity);
+        self.positions[0] = sun;
+        self.velocities[0] = -momentum / sun_gm;
+        for position in &mut self.positions[1..] {
+            *position += sun;
+        }
+        Ok(())
+    }
+
+    /* Kick from the planets pulling on each other, heliocentric positions
+     * work as well as barycentric ones for the separations */
+    fn interaction_kick(&mut self, step: f64) {
+        let accelerations = self.accelerations(1);
+        for (velocity, acceleration) in self.velocities[1..].iter_mut().zip(&accelerations[1..]) {
+            *velocity += step * acceleration;
+        }
+    }
+
+    /* Every planet moves with the Sun's reflex velocity */
+    fn sun_drift(&mut self, step: f64) {
+        let momentum = self.gms[1..]
+            .iter()
+            .zip(&self.velocities[1..])
+            .fold(Vector3::zeros(), |sum, (gm, velocity)| sum + *gm * velocity);
+        let shift = step * momentum / self.gms[0];
+        for position in &mut self.positions[1..] {
+            *position += shift;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::body::Body;
+    use crate::constants::{JUPITERGM, SATURNGM, SOLARGM};
+    use std::f64::consts::PI;
+
+    fn sun() -> Particle {
+        Particle::new("Sun", SOLARGM, Vector3::zeros(), Vector3::zeros())
+    }
+
+    /* Starting at periapsis of an orbit of semi-major axis a about the Sun */
+    fn planet(name: &str, gm: f64, a: f64, e: f64) -> Particle {
+        let radius = a * (1.0 - e);
+        let speed = ((SOLARGM + gm) * (1.0 + e) / radius).sqrt();
+        Particle::new(
+            name,
+            gm,
+            Vector3::new(radius, 0.0, 0.0),
+            Vector3::new(0.0, speed * 0.95, speed * 0.05_f64.sqrt()),
+        )
+    }
+
+    fn outer_planets() -> NBody {
+        NBody::new(vec![
+            sun(),
+            planet("Jupiter", JUPITERGM, 5.2, 0.05),
+            planet("Saturn", SATURNGM, 9.6, 0.06),
+        ])
+    }
+
+    /* Largest energy drift over each period */
+    fn energy_drift(
+        system: &NBody,
+        method: Method,
+        step: f64,
+        period: f64,
+        periods: usize,
+    ) -> Vec<f64> {
+        let mut integrator = Symplectic::new(system, method, step).unwrap();
+        (1..=periods)
+            .map(|n| {
+                let mut largest: f64 = 0.0;
+                while integrator.time + step <= n as f64 * period {
+                    integrator.advance().unwrap();
+                    largest = largest.max(integrator.drift().energy);
+                }
+                largest
+            })
+            .collect()
+    }
+
+    /* How far a massless body ends up from its Kepler orbit about the Sun */
+    fn kepler_error(method: Method, step: f64, duration: f64) -> f64 {
+        let test = planet("Comet", 0.0, 1.0, 0.5);
+        let exact = Body::new(test.position, test.velocity, SOLARGM)
+            .position_at_time(duration)
+            .unwrap();
+        let mut integrator = Symplectic::new(&NBody::new(vec![sun(), test]), method, step).unwrap();
+        integrator.advance_to(duration).unwrap();
+        let particles = integrator.particles();
+        (particles[1].position - particles[0].position - exact).norm()
+    }
+
+    #[test]
+    fn energy_error_stays_bounded() {
+        let period = 2.0 * PI * (5.2_f64.powi(3) / SOLARGM).sqrt();
+        let cases = [
+            (Method::Leapfrog, 10.0, 1e-7),
+            (Method::Yoshida4, 10.0, 1e-10),
+            (Method::Yoshida6, 40.0, 1e-11),
+            (Method::WisdomHolman, 100.0, 1e-6),
+        ];
+        for &(method, step, bound) in cases.iter() {
+            // 50 orbits of Jupiter, about six centuries
+            let drift = energy_drift(&outer_planets(), method, step, period, 50);
+            let early = drift[..5].iter().cloned().fold(0.0, f64::max);
+            let late = drift[45..].iter().cloned().fold(0.0, f64::max);
+            assert!(early < bound && late < bound, "{:?}: {:?}", method, drift);
+            // An error that grows would be several times as big by now
+            assert!(late < 2.0 * early, "{:?}: {} then {}", method, early, late);
+        }
+    }
+
+    #[test]
+    fn halving_the_step_follows_the_order() {
+        // Global position error on an eccentric Kepler orbit
+        let cases = [
+            (Method::Leapfrog, 1.0, 2),
+            (Method::Yoshida4, 4.0, 4),
+            (Method::Yoshida6, 8.0, 6),
+        ];
+        for &(method, step, order) in cases.iter() {
+            let ratio = kepler_error(method, step, 368.0) / kepler_error(method, step / 2.0, 368.0);
+            let expected = 2_f64.powi(order);
+            assert!(
+                ratio > 0.8 * expected && ratio < 1.25 * expected,
+                "{:?}: {}",
+                method,
+                ratio
+            );
+        }
+
+        // Wisdom-Holman is exact for the Kepler part, so its energy error
+        // comes from the planets' pull on each other, still second order
+        let period = 2.0 * PI * (5.2_f64.powi(3) / SOLARGM).sqrt();
+        let coarse = energy_drift(&outer_planets(), Method::WisdomHolman, 100.0, period, 1);
+        let fine = energy_drift(&outer_planets(), Method::WisdomHolman, 50.0, period, 1);
+        let ratio = coarse[0] / fine[0];
+        assert!(ratio > 3.2 && ratio < 5.0, "{}", ratio);
+    }
+
+    #[test]
+    fn massless_body_is_exact_with_wisdom_holman() {
+        let error = kepler_error(Method::WisdomHolman, 30.0, 360.0);
+        assert!(error < 1e-12, "{}", error);
+    }
+
+    #[test]
+    fn needs_a_usable_step() {
+        let system = outer_planets();
+        assert!(Symplectic::new(&system, Method::Leapfrog, 0.0).is_err());
+        assert!(Symplectic::new(&system, Method::Leapfrog, f64::NAN).is_err());
+        assert!(Symplectic::new(&NBody::new(vec![]), Method::Leapfrog, 1.0).is_err());
+    }
+}
@@ -0,0 +1,184 @@
+/*!
+ * Third-body perturbations. A body orbiting a central body also gets pulled
+ * on by everything else (the Sun and Moon for an Earth orbiter, Neptune
+ * for Pluto about the Sun). Since the central body is being pulled on too
+ * and the frame moves with it, only the difference between the two pulls
+ * shows up: the direct term on the orbiting body minus the indirect term
+ * on the central body,
+ *
+ *   a = gm_k ((r_k - r) / |r_k - r|^3 - r_k / |r_k|^3)
+ *
+ * For a far away perturber those two terms are nearly equal, so the
+ * difference is worked out with Battin's f(q) instead of subtracting them
+ * (Battin, section 8.4).
+ *
+ * Each perturber moves along its own two-body orbit about the central body,
+ * propagated from its state at time zero.
+ */
+use nalgebra::Vector3;
+
+use super::body::Body;
+use super::force::ForceModel;
+
+/// A perturbing body with its state relative to the central body at time
+/// zero
+#[derive(Debug, Clone)]
+pub struct Perturber {
+    pub name: String,
+    /// Gravitational parameter, AU^3/day^2
+    pub gm: f64,
+    pub state: Body,
+}
+
+impl Perturber {
+    pub fn new(name: &str, gm: f64, state: Body) -> Perturber {
+        Perturber {
+            name: String::from(name),
+            gm,
+            state,
+        }
+    }
+
+    /// Perturber from heliocentric states of both it and the central body.
+    /// mu is for its two-body orbit about the central body, usually the sum
+    /// of the two gms.
+    pub fn from_heliocentric(
+        name: &str,
+        gm: f64,
+        state: &Body,
+        central: &Body,
+        mu: f64,
+    ) -> Perturber {
+        Perturber::new(name, gm, state.relative_to(central, mu))
+    }
+
+    /// The Sun as seen from a planet whose heliocentric state is central
+    pub fn sun(gm: f64, central: &Body) -> Perturber {
+        Perturber::new(
+            "Sun",
+            gm,
+            Body::new(-central.position, -central.velocity, central.mu),
+        )
+    }
+
+    /// Position relative to the central body at a time. One that can't be
+    /// propagated stays where it was.
+    pub fn position_at_time(&self, time: f64) -> Vector3<f64> {
+        self.state
+            .position_at_time(time)
+            .unwrap_or(self.state.position)
+    }
+
+    /// Direct minus indirect acceleration on a body at position
+    pub fn acceleration_at(&self, time: f64, position: &Vector3<f64>) -> Vector3<f64> {
+        let perturber = self.position_at_time(time);
+        let separation = position - perturber;
+        let q = position.dot(&(position - 2.0 * perturber)) / perturber.norm_squared();
+        let f = q * (3.0 + 3.0 * q + q.powi(2)) / (1.0 + (1.0 + q).powf(1.5));
+        -self.gm / separation.norm().powi(3) * (position + f * perturber)
+    }
+}
+
+/// Third-body perturbations from any number of perturbers
+#[derive(Debug, Clone)]
+pub struct ThirdBody {
+    pub perturbers: Vec<Perturber>,
+}
+
+impl ThirdBody {
+    pub fn new(perturbers: Vec<Perturber>) -> ThirdBody {
+        ThirdBody { perturbers }
+    }
+}
+
+impl ForceModel for Perturber {
+    fn acceleration(&self, time: f64, position: &Vector3<f64>, _: &Vector3<f64>) -> Vector3<f64> {
+        self.acceleration_at(time, position)
+    }
+}
+
+impl ForceModel for ThirdBody {
+    fn acceleration(&self, time: f64, position: &Vector3<f64>, _: &Vector3<f64>) -> Vector3<f64> {
+        self.perturbers
+            .iter()
+            .map(|perturber| perturber.acceleration_at(time, position))
+            .fold(Vector3::zeros(), |total, acceleration| total + acceleration)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{EARTHGM, SOLARGM};
+
+    /* A perturber at rest at distance from the central body, so it's
+     * still there at time zero */
+    fn perturber(distance: f64) -> Perturber {
+        let direction = Vector3::new(0.6, -0.48, 0.64);
+        Perturber::new(
+            "Sun",
+            SOLARGM,
+            Body::new(distance * direction, Vector3::new(0.0, 1e-3, 0.0), SOLARGM),
+        )
+    }
+
+    fn direct(perturber: &Perturber, position: &Vector3<f64>) -> Vector3<f64> {
+        let r_k = perturber.state.position;
+        let d = r_k - position;
+        perturber.gm * (d / d.norm().powi(3) - r_k / r_k.norm().powi(3))
+    }
+
+    #[test]
+    fn battin_matches_direct_difference() {
+        let position = Vector3::new(3e-5, 2e-5, -1e-5);
+        // Out to a thousand times as far away the plain difference still
+        // keeps about 12 digits
+        for &ratio in [2.0, 10.0, 1e3].iter() {
+            let sun = perturber(ratio * position.norm());
+            let expected = direct(&sun, &position);
+            let acceleration = sun.acceleration_at(0.0, &position);
+            assert!(
+                (acceleration - expected).norm() < 1e-10 * expected.norm(),
+                "ratio {}: {} vs {}",
+                ratio,
+                acceleration,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn battin_matches_tidal_term_when_far() {
+        // At a separation ratio of 1e7 the plain difference has lost most
+        // of its digits, but the leading tidal term is good to about 1e-7
+        let position = Vector3::new(3e-5, 2e-5, -1e-5);
+        let sun = perturber(1e7 * position.norm());
+        let r_k = sun.state.position;
+        let r_hat = r_k.normalize();
+        let tidal = sun.gm / r_k.norm().powi(3) * (3.0 * position.dot(&r_hat) * r_hat - position);
+        let acceleration = sun.acceleration_at(0.0, &position);
+        assert!((acceleration - tidal).norm() < 1e-6 * tidal.norm());
+    }
+
+    #[test]
+    fn third_body_sums_perturbers() {
+        let position = Vector3::new(3e-5, 2e-5, -1e-5);
+        let sun = perturber(1.0);
+        let moon = Perturber::new(
+            "Moon",
+            EARTHGM / 81.3,
+            Body::new(
+                Vector3::new(-2.57e-3, 0.0, 0.0),
+                Vector3::new(0.0, 5.9e-4, 0.0),
+                EARTHGM,
+            ),
+        );
+        let total = ThirdBody::new(vec![sun.clone(), moon.clone()]).acceleration(
+            0.0,
+            &position,
+            &Vector3::zeros(),
+        );
+        let expected = direct(&sun, &position) + direct(&moon, &position);
+        assert!((total - expected).norm() < 1e-10 * expected.norm());
+    }
+}
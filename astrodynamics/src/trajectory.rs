@@ -0,0 +1,183 @@
+/*!
+ * Time-tagged trajectories. Propagating a Body one epoch at a time through
+ * position_at_time and velocity_at_time redoes all of the setup for every
+ * call; Body::propagate sets the orbit up once and then runs through a whole
+ * grid of epochs, either collecting the states into a Trajectory or handing
+ * them out lazily one at a time.
+ */
+use nalgebra::Vector3;
+
+use super::body::Body;
+use super::error::OrbitError;
+use super::universal::UniversalPropagator;
+
+/// Position and velocity at an epoch, with time in days from the starting
+/// state (AU and AU/day like Body)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct State {
+    pub time: f64,
+    pub position: Vector3<f64>,
+    pub velocity: Vector3<f64>,
+}
+
+impl State {
+    /// Turn the state back into a Body about a central body with
+    /// gravitational parameter mu
+    pub fn to_body(&self, mu: f64) -> Body {
+        Body::new(self.position, self.velocity, mu)
+    }
+}
+
+/// States of one body at a series of epochs, all about the same central body
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trajectory {
+    pub mu: f64,
+    pub states: Vec<State>,
+}
+
+impl Trajectory {
+    pub fn new(mu: f64) -> Trajectory {
+        Trajectory {
+            mu,
+            states: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, State> {
+        self.states.iter()
+    }
+
+    /// Epochs of every state in the trajectory
+    pub fn times(&self) -> impl Iterator<Item = f64> + '_ {
+        self.states.iter().map(|state| state.time)
+    }
+
+    /// The state at an index as a Body
+    pub fn body(&self, index: usize) -> Option<Body> {
+        self.states.get(index).map(|state| state.to_body(self.mu))
+    }
+}
+
+impl<'a> IntoIterator for &'a Trajectory {
+    type Item = &'a State;
+    type IntoIter = std::slice::Iter<'a, State>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.states.iter()
+    }
+}
+
+/// Lazy iterator over the states of a body at a series of epochs, made by
+/// Body::propagate_iter
+pub struct Propagation<I> {
+    propagator: Result<UniversalPropagator, OrbitError>,
+    times: I,
+}
+
+impl<I: Iterator<Item = f64>> Iterator for Propagation<I> {
+    type Item = Result<State, OrbitError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let time = self.times.next()?;
+        Some(self.propagator.and_then(|propagator| {
+            let (position, velocity) = propagator.state_at(time)?;
+            Ok(State {
+                time,
+                position,
+                velocity,
+            })
+        }))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.times.size_hint()
+    }
+}
+
+impl Body {
+    /// States at every epoch in times (days from now), worked out lazily.
+    /// The orbit is only set up once no matter how many epochs there are.
+    pub fn propagate_iter<I>(&self, times: I) -> Propagation<I::IntoIter>
+    where
+        I: IntoIterator<Item = f64>,
+    {
+        Propagation {
+            propagator: UniversalPropagator::new(self.position, self.velocity, self.mu),
+            times: times.into_iter(),
+        }
+    }
+
+    /// Trajectory through every epoch in times (days from now). Stops at the
+    /// first epoch that can't be propagated and returns its error.
+    pub fn propagate<I>(&self, times: I) -> Result<Trajectory, OrbitError>
+    where
+        I: IntoIterator<Item = f64>,
+    {
+        Ok(Trajectory {
+            mu: self.mu,
+            states: self.propagate_iter(times).collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::SOLARGM;
+    use crate::units::Time;
+
+    fn orbit() -> Body {
+        Body::new(
+            Vector3::new(0.9, 0.1, 0.0),
+            Vector3::new(-0.004, 0.021, 0.001),
+            SOLARGM,
+        )
+    }
+
+    #[test]
+    fn grid_matches_single_epochs() {
+        let body = orbit();
+        let times = (-5..40).map(|step| f64::from(step) * 37.5);
+        let trajectory = body.propagate(times.clone()).unwrap();
+        assert_eq!(trajectory.len(), 45);
+        for (state, time) in trajectory.iter().zip(times) {
+            assert_eq!(state.time, time);
+            let position = body.position_after(Time::days(time)).unwrap().to_au();
+            let velocity = body
+                .velocity_after(Time::days(time))
+                .unwrap()
+                .to_au_per_day();
+            assert!((state.position - position).norm() < 1e-14 * position.norm());
+            assert!((state.velocity - velocity).norm() < 1e-14 * velocity.norm());
+        }
+        assert_eq!(trajectory.body(0).unwrap().mu, SOLARGM);
+    }
+
+    #[test]
+    fn error_part_way_is_surfaced() {
+        let times = vec![10.0, 20.0, f64::NAN, 30.0];
+        let states: Vec<_> = orbit().propagate_iter(times.clone()).collect();
+        assert!(states[0].is_ok() && states[1].is_ok() && states[3].is_ok());
+        assert_eq!(states[2], Err(OrbitError::DegenerateState));
+        assert_eq!(
+            orbit().propagate(times).unwrap_err(),
+            OrbitError::DegenerateState
+        );
+    }
+
+    #[test]
+    fn degenerate_start_fails_every_epoch() {
+        let body = Body::new(Vector3::zeros(), Vector3::new(0.0, 0.02, 0.0), SOLARGM);
+        assert!(body
+            .propagate_iter(vec![1.0, 2.0])
+            .all(|state| state == Err(OrbitError::DegenerateState)));
+    }
+}
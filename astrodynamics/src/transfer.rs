@@ -0,0 +1,404 @@
+/*!
+ * Coplanar orbit transfers. Hohmann and bi-elliptic transfers go between
+ * two closed orbits that share a plane, with every burn made along the
+ * velocity at an apse of the transfer ellipse. The target is only used as
+ * an orbit here, where it is along that orbit when the spacecraft arrives
+ * doesn't matter. two_impulse does care: it uses Lambert to meet the
+ * target body itself after a given time of flight.
+ *
+ * Every transfer starts from the departure body's current state, so burn
+ * times are in days from now, and delta-v is in AU/day like Body.
+ */
+use nalgebra::Vector3;
+use std::f64::consts::PI;
+
+use super::body::{Body, OrbitType};
+use super::error::OrbitError;
+use super::lambert::{lambert, Direction};
+
+/* Orbits with a smaller eccentricity than this are treated as circular,
+ * so the apses aren't used to pick a departure point */
+const CIRCULAR: f64 = 1e-8;
+
+/* Largest angle between the two angular momentum vectors, in radians,
+ * that still counts as coplanar */
+const COPLANAR: f64 = 1e-6;
+
+/// An impulsive burn, time in days from the start of the transfer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Burn {
+    pub time: f64,
+    pub delta_v: Vector3<f64>,
+}
+
+impl Burn {
+    pub fn magnitude(&self) -> f64 {
+        self.delta_v.norm()
+    }
+}
+
+/// Where on the departure orbit the first burn is made
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Apse {
+    Periapsis,
+    Apoapsis,
+}
+
+/// The burns of a transfer and the state right after each one. The first
+/// state is on the first transfer orbit and the last is on the target orbit.
+#[derive(Debug, Clone)]
+pub struct CoplanarTransfer {
+    pub burns: Vec<Burn>,
+    pub states: Vec<Body>,
+}
+
+impl CoplanarTransfer {
+    /// Time from the first burn to the last, days
+    pub fn transfer_time(&self) -> f64 {
+        match (self.burns.first(), self.burns.last()) {
+            (Some(first), Some(last)) => last.time - first.time,
+            _ => 0.0,
+        }
+    }
+
+    pub fn total_delta_v(&self) -> f64 {
+        self.burns.iter().map(Burn::magnitude).sum()
+    }
+}
+
+/// Hohmann transfer from one orbit to another. The first burn is at
+/// depart_at on the departure orbit (or right away if it's circular) and
+/// the second is half a transfer orbit later on the far side.
+pub fn hohmann(from: &Body, to: &Body, depart_at: Apse) -> Result<CoplanarTransfer, OrbitError> {
+    check_orbits(from, to)?;
+    let wait = time_to_apse(from, depart_at)?;
+    let start = from.at_time(wait)?;
+    let (arrival_point, target_velocity) = crossing(to, -start.position);
+
+    let leg = transfer_orbit(&start, arrival_point.norm());
+    let leg_time = PI * (leg.semi_major_axis().powi(3) / leg.mu).sqrt();
+    let arrival = leg.at_time(leg_time)?;
+
+    Ok(CoplanarTransfer {
+        burns: vec![
+            Burn {
+                time: wait,
+                delta_v: leg.velocity - start.velocity,
+            },
+            Burn {
+                time: wait + leg_time,
+                delta_v: target_velocity - arrival.velocity,
+            },
+        ],
+        states: vec![leg, Body::new(arrival.position, target_velocity, to.mu)],
+    })
+}
+
+/// Bi-elliptic transfer from one orbit to another through an intermediate
+/// radius on the far side, which has to be at least as far out as both
+/// orbits. Three burns: out to the intermediate radius, back in to the
+/// target orbit, and onto the target orbit.
+pub fn bi_elliptic(
+    from: &Body,
+    to: &Body,
+    depart_at: Apse,
+    intermediate_radius: f64,
+) -> Result<CoplanarTransfer, OrbitError> {
+    check_orbits(from, to)?;
+    let wait = time_to_apse(from, depart_at)?;
+    let start = from.at_time(wait)?;
+    let (arrival_point, target_velocity) = crossing(to, start.position);
+    if intermediate_radius < start.position.norm() || intermediate_radius < arrival_point.norm() {
+        return Err(OrbitError::NoSolution);
+    }
+
+    let first = transfer_orbit(&start, intermediate_radius);
+    let first_time = PI * (first.semi_major_axis().powi(3) / first.mu).sqrt();
+    let turn = first.at_time(first_time)?;
+
+    let second = transfer_orbit(&turn, arrival_point.norm());
+    let second_time = PI * (second.semi_major_axis().powi(3) / second.mu).sqrt();
+    let arrival = second.at_time(second_time)?;
+
+    Ok(CoplanarTransfer {
+        burns: vec![
+            Burn {
+                time: wait,
+                delta_v: first.velocity - start.velocity,
+            },
+            Burn {
+                time: wait + first_time,
+                delta_v: second.velocity - turn.velocity,
+            },
+            Burn {
+                time: wait + first_time + second_time,
+                delta_v: target_velocity - arrival.velocity,
+            },
+        ],
+        states: vec![
+            first,
+            second,
+            Body::new(arrival.position, target_velocity, to.mu),
+        ],
+    })
+}
+
+/// Two-impulse transfer that meets the target body, leaving after wait days
+/// and arriving time_of_flight days later. Unlike hohmann and bi_elliptic
+/// the target's position along its orbit matters, so this is the one to use
+/// for rendezvous and for checking phasing.
+pub fn two_impulse(
+    from: &Body,
+    to: &Body,
+    wait: f64,
+    time_of_flight: f64,
+) -> Result<CoplanarTransfer, OrbitError> {
+    check_orbits(from, to)?;
+    let start = from.at_time(wait)?;
+    let target = to.at_time(wait + time_of_flight)?;
+    let direction = if start
+        .position
+        .cross(&target.position)
+        .dot(&from.angular_momentum())
+        >= 0.0
+    {
+        Direction::Short
+    } else {
+        Direction::Long
+    };
+    let solution = lambert(
+        start.position,
+        target.position,
+        time_of_flight,
+        from.mu,
+        direction,
+    )?;
+
+    Ok(CoplanarTransfer {
+        burns: vec![
+            Burn {
+                time: wait,
+                delta_v: solution.departure.velocity - start.velocity,
+            },
+            Burn {
+                time: wait + time_of_flight,
+                delta_v: target.velocity - solution.arrival.velocity,
+            },
+        ],
+        states: vec![solution.departure, target],
+    })
+}
+
+/* Both orbits have to be closed, around the same central body and going
+ * the same way in the same plane */
+fn check_orbits(from: &Body, to: &Body) -> Result<(), OrbitError> {
+    for body in &[from, to] {
+        match body.orbit_type {
+            OrbitType::Circular | OrbitType::Elliptic => {}
+            _ => return Err(OrbitError::OpenOrbit),
+        }
+    }
+    if from.mu != to.mu {
+        return Err(OrbitError::CentralBodyMismatch);
+    }
+    let h_from = from.angular_momentum().normalize();
+    let h_to = to.angular_momentum().normalize();
+    if h_from.cross(&h_to).norm() > COPLANAR || h_from.dot(&h_to) < 0.0 {
+        return Err(OrbitError::NotCoplanar);
+    }
+    Ok(())
+}
+
+/* Days until the body reaches an apse, zero for circular orbits */
+fn time_to_apse(body: &Body, apse: Apse) -> Result<f64, OrbitError> {
+    if body.eccentricity() < CIRCULAR {
+        return Ok(0.0);
+    }
+    let t_anom = match apse {
+        Apse::Periapsis => 0.0,
+        Apse::Apoapsis => PI,
+    };
+    body.time_until_true_anomaly(t_anom)
+}
+
+/* Position and velocity where an orbit crosses the direction dir */
+fn crossing(orbit: &Body, dir: Vector3<f64>) -> (Vector3<f64>, Vector3<f64>) {
+    let h = orbit.angular_momentum();
+    let h_hat = h.normalize();
+    let dir = (dir - dir.dot(&h_hat) * h_hat).normalize();
+    let e_vec = orbit.eccentricity_vector();
+    let e = e_vec.norm();
+
+    // Perifocal axes, circular orbits measure from dir itself
+    let e_p = if e < CIRCULAR { dir } else { e_vec / e };
+    let e_q = h_hat.cross(&e_p);
+    let t_anom = dir.dot(&e_q).atan2(dir.dot(&e_p));
+    let p = h.norm_squared() / orbit.mu;
+    let radius = p / (1.0 + e * t_anom.cos());
+    let velocity = (orbit.mu / p).sqrt() * (-t_anom.sin() * e_p + (e + t_anom.cos()) * e_q);
+    (radius * dir, velocity)
+}
+
+/* Orbit that leaves start's position with a purely tangential velocity and
+ * reaches radius on the far side */
+pub(crate) fn transfer_orbit(start: &Body, radius: f64) -> Body {
+    let r_mag = start.position.norm();
+    let a = (r_mag + radius) / 2.0;
+    let speed = (start.mu * (2.0 / r_mag - 1.0 / a)).sqrt();
+    let e_theta = start.angular_momentum().cross(&start.position).normalize();
+    Body::new(start.position, speed * e_theta, start.mu)
+}
+
+impl Body {
+    /// Days until a closed orbit next reaches a true anomaly
+    pub fn time_until_true_anomaly(&self, t_anom: f64) -> Result<f64, OrbitError> {
+        match self.orbit_type {
+            OrbitType::Circular | OrbitType::Elliptic => {}
+            _ => return Err(OrbitError::OpenOrbit),
+        }
+        let e = self.eccentricity();
+        let mean = |angle: f64| {
+            let e_anom = self.true_to_eccentric(angle);
+            e_anom - e * e_anom.sin()
+        };
+        let n = (self.mu / self.semi_major_axis().powi(3)).sqrt();
+        Ok((mean(t_anom) - mean(self.true_anomaly())).rem_euclid(2.0 * PI) / n)
+    }
+
+    /// Days until a closed orbit next crosses the line from the central
+    /// body along direction, which should be in the orbit plane
+    pub fn time_until_crossing(&self, direction: Vector3<f64>) -> Result<f64, OrbitError> {
+        let h_hat = self.angular_momentum().normalize();
+        let e_vec = self.eccentricity_vector();
+        if e_vec.norm() >= CIRCULAR {
+            let e_hat = e_vec.normalize();
+            let t_anom = direction
+                .dot(&h_hat.cross(&e_hat))
+                .atan2(direction.dot(&e_hat));
+            return self.time_until_true_anomaly(t_anom);
+        }
+        // No periapsis to measure from on a circular orbit, so go by the
+        // angle from where the body is now
+        let r_hat = self.position.normalize();
+        let angle = r_hat
+            .cross(&direction)
+            .dot(&h_hat)
+            .atan2(r_hat.dot(&direction));
+        let n = (self.mu / self.semi_major_axis().powi(3)).sqrt();
+        Ok(angle.rem_euclid(2.0 * PI) / n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::units::{GravParam, Length, Speed};
+
+    fn earth_mu() -> f64 {
+        GravParam::km3_per_s2(398_600.441_8).to_au3_per_day2()
+    }
+
+    /* Circular orbit in the xy plane, angle radians from x */
+    fn circular(radius: f64, angle: f64, mu: f64) -> Body {
+        let (sin, cos) = angle.sin_cos();
+        Body::new(
+            radius * Vector3::new(cos, sin, 0.0),
+            (mu / radius).sqrt() * Vector3::new(-sin, cos, 0.0),
+            mu,
+        )
+    }
+
+    fn km_per_s(delta_v: f64) -> f64 {
+        Speed::au_per_day(delta_v).to_km_per_s()
+    }
+
+    #[test]
+    fn hohmann_leo_to_geo() {
+        // Vallado's example 6-1
+        let mu = earth_mu();
+        let leo = Length::km(6569.4781).to_au();
+        let geo = Length::km(42159.4856).to_au();
+        let transfer = hohmann(
+            &circular(leo, 0.0, mu),
+            &circular(geo, 1.0, mu),
+            Apse::Periapsis,
+        )
+        .unwrap();
+        assert_eq!(transfer.burns.len(), 2);
+        assert!((km_per_s(transfer.total_delta_v()) - 3.935224).abs() < 1e-5);
+        assert!((transfer.transfer_time() * 24.0 - 5.256713).abs() < 1e-5);
+        let arrival = transfer.states.last().unwrap();
+        assert!((arrival.position.norm() - geo).abs() < 1e-9 * geo);
+        assert!(arrival.eccentricity() < 1e-9);
+    }
+
+    #[test]
+    fn bi_elliptic_beats_hohmann_past_the_ratio() {
+        let mu = earth_mu();
+        let inner = Length::km(7000.0).to_au();
+        let from = circular(inner, 0.0, mu);
+        let cost = |ratio: f64, intermediate: f64| {
+            let to = circular(ratio * inner, 0.0, mu);
+            let hohmann = hohmann(&from, &to, Apse::Periapsis).unwrap();
+            let bi_elliptic =
+                bi_elliptic(&from, &to, Apse::Periapsis, intermediate * inner).unwrap();
+            (hohmann.total_delta_v(), bi_elliptic.total_delta_v())
+        };
+        // Past r2/r1 = 15.58 any intermediate radius beyond the target wins
+        for &intermediate in &[17.0, 20.0, 60.0] {
+            let (hohmann, bi_elliptic) = cost(16.0, intermediate);
+            assert!(bi_elliptic < hohmann);
+        }
+        // Below 11.94 Hohmann always wins
+        for &intermediate in &[40.0, 1000.0] {
+            let (hohmann, bi_elliptic) = cost(10.0, intermediate);
+            assert!(bi_elliptic > hohmann);
+        }
+    }
+
+    #[test]
+    fn two_impulse_with_hohmann_phasing_costs_the_same() {
+        let mu = earth_mu();
+        let (inner, outer) = (Length::km(7000.0).to_au(), Length::km(21000.0).to_au());
+        let from = circular(inner, 0.0, mu);
+        let hohmann = hohmann(&from, &circular(outer, 0.0, mu), Apse::Periapsis).unwrap();
+        let time_of_flight = hohmann.transfer_time();
+        // Start the target where it reaches the far side as the transfer does
+        let lead = PI - (mu / outer.powi(3)).sqrt() * time_of_flight;
+        // A little short of 180 degrees, where Lambert still has a plane
+        // to work in
+        let target = circular(outer, lead - 1e-4, mu);
+        let transfer = two_impulse(&from, &target, 0.0, time_of_flight).unwrap();
+        let difference = (transfer.total_delta_v() - hohmann.total_delta_v()).abs();
+        assert!(difference < 1e-6 * hohmann.total_delta_v());
+    }
+
+    #[test]
+    fn orbits_have_to_share_a_plane() {
+        let mu = earth_mu();
+        let from = circular(Length::km(7000.0).to_au(), 0.0, mu);
+        let to = circular(Length::km(9000.0).to_au(), 0.0, mu);
+        let speed = to.velocity.norm();
+        let tilted = Body::new(
+            to.position,
+            speed * Vector3::new(0.0, 0.01_f64.cos(), 0.01_f64.sin()),
+            mu,
+        );
+        let retrograde = Body::new(to.position, -to.velocity, mu);
+        for other in &[tilted, retrograde] {
+            assert_eq!(
+                hohmann(&from, other, Apse::Periapsis).unwrap_err(),
+                OrbitError::NotCoplanar
+            );
+            assert_eq!(
+                two_impulse(&from, other, 0.0, 0.1).unwrap_err(),
+                OrbitError::NotCoplanar
+            );
+        }
+        let elsewhere = Body::new(to.position, to.velocity, 2.0 * mu);
+        assert_eq!(
+            bi_elliptic(&from, &elsewhere, Apse::Periapsis, 1.0).unwrap_err(),
+            OrbitError::CentralBodyMismatch
+        );
+    }
+}
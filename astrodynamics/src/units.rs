@@ -0,0 +1,268 @@
+/*!
+ * Unit-safe quantities. Each type stores its value in the library's
+ * working units (AU and days) and can only be built or read back through a
+ * constructor/accessor that names the unit, so a km/s velocity can't be
+ * handed to something expecting AU/day without the conversion happening.
+ */
+use nalgebra::Vector3;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/// Kilometers in one astronomical unit
+pub const AUTOKM: f64 = 1.49597870700e8;
+/// Meters in one astronomical unit
+pub const AUTOM: f64 = AUTOKM * 1000.0;
+/// Seconds in one day
+pub const DAYTOSEC: f64 = 24.0 * 3600.0;
+
+/// A distance, stored in AU
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Length(f64);
+
+/// A span of time, stored in days
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Time(f64);
+
+/// A scalar speed, stored in AU/day
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Speed(f64);
+
+/// A gravitational parameter, stored in AU^3/day^2
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct GravParam(f64);
+
+/// A position vector, stored in AU
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Position(Vector3<f64>);
+
+/// A velocity vector, stored in AU/day
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Velocity(Vector3<f64>);
+
+impl Length {
+    pub fn au(value: f64) -> Length {
+        Length(value)
+    }
+
+    pub fn km(value: f64) -> Length {
+        Length(value / AUTOKM)
+    }
+
+    pub fn m(value: f64) -> Length {
+        Length(value / AUTOM)
+    }
+
+    pub fn to_au(self) -> f64 {
+        self.0
+    }
+
+    pub fn to_km(self) -> f64 {
+        self.0 * AUTOKM
+    }
+
+    pub fn to_m(self) -> f64 {
+        self.0 * AUTOM
+    }
+}
+
+impl Time {
+    pub fn days(value: f64) -> Time {
+        Time(value)
+    }
+
+    pub fn seconds(value: f64) -> Time {
+        Time(value / DAYTOSEC)
+    }
+
+    pub fn to_days(self) -> f64 {
+        self.0
+    }
+
+    pub fn to_seconds(self) -> f64 {
+        self.0 * DAYTOSEC
+    }
+}
+
+impl Speed {
+    pub fn au_per_day(value: f64) -> Speed {
+        Speed(value)
+    }
+
+    pub fn km_per_s(value: f64) -> Speed {
+        Speed(value * DAYTOSEC / AUTOKM)
+    }
+
+    pub fn m_per_s(value: f64) -> Speed {
+        Speed(value * DAYTOSEC / AUTOM)
+    }
+
+    pub fn to_au_per_day(self) -> f64 {
+        self.0
+    }
+
+    pub fn to_km_per_s(self) -> f64 {
+        self.0 * AUTOKM / DAYTOSEC
+    }
+
+    pub fn to_m_per_s(self) -> f64 {
+        self.0 * AUTOM / DAYTOSEC
+    }
+}
+
+impl GravParam {
+    pub fn au3_per_day2(value: f64) -> GravParam {
+        GravParam(value)
+    }
+
+    pub fn km3_per_s2(value: f64) -> GravParam {
+        GravParam(value * DAYTOSEC.powi(2) / AUTOKM.powi(3))
+    }
+
+    pub fn m3_per_s2(value: f64) -> GravParam {
+        GravParam(value * DAYTOSEC.powi(2) / AUTOM.powi(3))
+    }
+
+    pub fn to_au3_per_day2(self) -> f64 {
+        self.0
+    }
+
+    pub fn to_km3_per_s2(self) -> f64 {
+        self.0 * AUTOKM.powi(3) / DAYTOSEC.powi(2)
+    }
+
+    pub fn to_m3_per_s2(self) -> f64 {
+        self.0 * AUTOM.powi(3) / DAYTOSEC.powi(2)
+    }
+}
+
+impl Position {
+    pub fn au(value: Vector3<f64>) -> Position {
+        Position(value)
+    }
+
+    pub fn km(value: Vector3<f64>) -> Position {
+        Position(value / AUTOKM)
+    }
+
+    pub fn m(value: Vector3<f64>) -> Position {
+        Position(value / AUTOM)
+    }
+
+    pub fn to_au(self) -> Vector3<f64> {
+        self.0
+    }
+
+    pub fn to_km(self) -> Vector3<f64> {
+        self.0 * AUTOKM
+    }
+
+    pub fn to_m(self) -> Vector3<f64> {
+        self.0 * AUTOM
+    }
+
+    pub fn norm(&self) -> Length {
+        Length(self.0.norm())
+    }
+}
+
+impl Velocity {
+    pub fn au_per_day(value: Vector3<f64>) -> Velocity {
+        Velocity(value)
+    }
+
+    pub fn km_per_s(value: Vector3<f64>) -> Velocity {
+        Velocity(value * DAYTOSEC / AUTOKM)
+    }
+
+    pub fn m_per_s(value: Vector3<f64>) -> Velocity {
+        Velocity(value * DAYTOSEC / AUTOM)
+    }
+
+    pub fn to_au_per_day(self) -> Vector3<f64> {
+        self.0
+    }
+
+    pub fn to_km_per_s(self) -> Vector3<f64> {
+        self.0 * AUTOKM / DAYTOSEC
+    }
+
+    pub fn to_m_per_s(self) -> Vector3<f64> {
+        self.0 * AUTOM / DAYTOSEC
+    }
+
+    pub fn norm(&self) -> Speed {
+        Speed(self.0.norm())
+    }
+}
+
+/* Same-unit arithmetic and scaling, which can't mix up units */
+macro_rules! quantity_ops {
+    ($($quantity:ident),*) => {
+        $(
+            impl Add for $quantity {
+                type Output = $quantity;
+                fn add(self, other: $quantity) -> $quantity {
+                    $quantity(self.0 + other.0)
+                }
+            }
+
+            impl Sub for $quantity {
+                type Output = $quantity;
+                fn sub(self, other: $quantity) -> $quantity {
+                    $quantity(self.0 - other.0)
+                }
+            }
+
+            impl Neg for $quantity {
+                type Output = $quantity;
+                fn neg(self) -> $quantity {
+                    $quantity(-self.0)
+                }
+            }
+
+            impl Mul<f64> for $quantity {
+                type Output = $quantity;
+                fn mul(self, scale: f64) -> $quantity {
+                    $quantity(self.0 * scale)
+                }
+            }
+
+            impl Div<f64> for $quantity {
+                type Output = $quantity;
+                fn div(self, scale: f64) -> $quantity {
+                    $quantity(self.0 / scale)
+                }
+            }
+        )*
+    };
+}
+
+quantity_ops!(Length, Time, Speed, GravParam, Position, Velocity);
+
+/* The few cross-unit products that come up in orbit work */
+impl Div<Time> for Length {
+    type Output = Speed;
+    fn div(self, time: Time) -> Speed {
+        Speed(self.0 / time.0)
+    }
+}
+
+impl Mul<Time> for Speed {
+    type Output = Length;
+    fn mul(self, time: Time) -> Length {
+        Length(self.0 * time.0)
+    }
+}
+
+impl Div<Time> for Position {
+    type Output = Velocity;
+    fn div(self, time: Time) -> Velocity {
+        Velocity(self.0 / time.0)
+    }
+}
+
+impl Mul<Time> for Velocity {
+    type Output = Position;
+    fn mul(self, time: Time) -> Position {
+        Position(self.0 * time.0)
+    }
+}
@@ -0,0 +1,402 @@
+/*!
+ * Universal-variable formulation of Kepler's problem. Instead of picking an
+ * eccentric, parabolic or hyperbolic anomaly based on the orbit type, the
+ * universal anomaly chi and the Stumpff functions c2 and c3 cover every
+ * conic at once, so a state can be propagated whether the orbit is
+ * circular, elliptic, parabolic or hyperbolic (Vallado, Algorithm 8).
+ */
+use nalgebra::Vector3;
+use std::f64::consts::PI;
+
+use super::body::Body;
+use super::error::OrbitError;
+
+/* Relative to chi once it's bigger than one, since chi scales with the
+ * square root of the distance units */
+const TOLERANCE: f64 = 1e-12;
+const MAX_ITERATIONS: usize = 100;
+
+/* Below this |psi| the closed forms of c2 and c3 lose precision and their
+ * series expansions are used instead */
+const PSI_SERIES: f64 = 1e-6;
+
+/* c4 and c5 are c2 and c3 with their leading term taken off, which cancels
+ * badly, so their series are used out to here */
+const PSI_SERIES_HIGHER: f64 = 0.1;
+
+/* alpha = 1/a, treat the orbit as parabolic when it's this close to zero */
+const ALPHA_PARABOLIC: f64 = 1e-6;
+
+/// Stumpff function c2(psi), psi = chi^2 / a
+pub fn c2_coeff(psi: f64) -> f64 {
+    if psi > PSI_SERIES {
+        (1.0 - psi.sqrt().cos()) / psi
+    } else if psi < -PSI_SERIES {
+        (1.0 - (-psi).sqrt().cosh()) / psi
+    } else {
+        0.5 - psi / 24.0 + psi.powi(2) / 720.0
+    }
+}
+
+/// Stumpff function c3(psi), psi = chi^2 / a
+pub fn c3_coeff(psi: f64) -> f64 {
+    if psi > PSI_SERIES {
+        let sqrt_psi = psi.sqrt();
+        (sqrt_psi - sqrt_psi.sin()) / psi.powi(3).sqrt()
+    } else if psi < -PSI_SERIES {
+        let sqrt_psi = (-psi).sqrt();
+        (sqrt_psi.sinh() - sqrt_psi) / (-psi).powi(3).sqrt()
+    } else {
+        1.0 / 6.0 - psi / 120.0 + psi.powi(2) / 5040.0
+    }
+}
+
+/// Stumpff function c4(psi), (1/2 - c2) / psi
+pub fn c4_coeff(psi: f64) -> f64 {
+    if psi.abs() > PSI_SERIES_HIGHER {
+        (0.5 - c2_coeff(psi)) / psi
+    } else {
+        stumpff_series(psi, 4)
+    }
+}
+
+/// Stumpff function c5(psi), (1/6 - c3) / psi
+pub fn c5_coeff(psi: f64) -> f64 {
+    if psi.abs() > PSI_SERIES_HIGHER {
+        (1.0 / 6.0 - c3_coeff(psi)) / psi
+    } else {
+        stumpff_series(psi, 5)
+    }
+}
+
+/* 1/n! - psi/(n + 2)! + psi^2/(n + 4)! - ..., which is c_n(psi) */
+fn stumpff_series(psi: f64, n: u32) -> f64 {
+    let mut term = 1.0 / (1..=n).map(f64::from).product::<f64>();
+    let mut sum = 0.0;
+    for k in 0..8 {
+        sum += term;
+        let next = f64::from(n + 2 * k);
+        term *= -psi / ((next + 1.0) * (next + 2.0));
+    }
+    sum
+}
+
+/// Universal-variable propagator for a single starting state. Everything
+/// that only depends on r_0 and v_0 is worked out once in new, so sending
+/// the same orbit to many epochs only costs the chi solve for each one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UniversalPropagator {
+    r_0: Vector3<f64>,
+    v_0: Vector3<f64>,
+    mu: f64,
+    sqrt_mu: f64,
+    r_0_mag: f64,
+    rv: f64,
+    alpha: f64,
+}
+
+impl UniversalPropagator {
+    pub fn new(r_0: Vector3<f64>, v_0: Vector3<f64>, mu: f64) -> Result<Self, OrbitError> {
+        let r_0_mag = r_0.norm();
+        let finite = r_0.iter().chain(v_0.iter()).all(|x| x.is_finite());
+        if !finite || r_0_mag == 0.0 || mu <= 0.0 {
+            return Err(OrbitError::DegenerateState);
+        }
+        Ok(UniversalPropagator {
+            r_0,
+            v_0,
+            mu,
+            sqrt_mu: mu.sqrt(),
+            r_0_mag,
+            rv: r_0.dot(&v_0),
+            alpha: -v_0.norm_squared() / mu + 2.0 / r_0_mag,
+        })
+    }
+
+    /// Position and velocity after a time of flight from the starting state
+    pub fn state_at(&self, time: f64) -> Result<(Vector3<f64>, Vector3<f64>), OrbitError> {
+        if !time.is_finite() {
+            return Err(OrbitError::DegenerateState);
+        }
+        if time == 0.0 {
+            return Ok((self.r_0, self.v_0));
+        }
+        let UniversalPropagator {
+            r_0,
+            v_0,
+            sqrt_mu,
+            r_0_mag,
+            alpha,
+            ..
+        } = *self;
+
+        // Whole revolutions don't change the state, so only propagate the
+        // leftover on closed orbits to keep chi small
+        let mut dt = time;
+        if alpha > ALPHA_PARABOLIC {
+            let period = 2.0 * PI / (sqrt_mu * alpha.powf(1.5));
+            dt %= period;
+        }
+        let chi = self.solve_chi(dt)?;
+
+        let (psi, c2, c3, r_mag) = self.radius(chi);
+        let f = 1.0 - chi.powi(2) / r_0_mag * c2;
+        let g = dt - chi.powi(3) / sqrt_mu * c3;
+        let g_dot = 1.0 - chi.powi(2) / r_mag * c2;
+        let f_dot = sqrt_mu / (r_mag * r_0_mag) * chi * (psi * c3 - 1.0);
+        Ok((f * r_0 + g * v_0, f_dot * r_0 + g_dot * v_0))
+    }
+
+    /// Universal anomaly chi after a time of flight, counting every whole
+    /// revolution on a closed orbit
+    pub fn universal_anomaly(&self, time: f64) -> Result<f64, OrbitError> {
+        if !time.is_finite() {
+            return Err(OrbitError::DegenerateState);
+        }
+        if self.alpha <= ALPHA_PARABOLIC {
+            return self.solve_chi(time);
+        }
+        // Each revolution adds 2 pi / sqrt(alpha) to chi
+        let period = 2.0 * PI / (self.sqrt_mu * self.alpha.powf(1.5));
+        let revolutions = (time / period).trunc();
+        Ok(self.solve_chi(time - revolutions * period)?
+            + revolutions * 2.0 * PI / self.alpha.sqrt())
+    }
+
+    /* Newton's method on the universal Kepler equation for chi */
+    fn solve_chi(&self, dt: f64) -> Result<f64, OrbitError> {
+        if dt == 0.0 {
+            return Ok(0.0);
+        }
+        let UniversalPropagator {
+            sqrt_mu,
+            r_0_mag,
+            rv,
+            ..
+        } = *self;
+        let mut chi = self.initial_chi(dt);
+        let mut iterations = 0;
+        loop {
+            let (psi, c2, c3, r_mag) = self.radius(chi);
+            let delta = (sqrt_mu * dt
+                - chi.powi(3) * c3
+                - (rv / sqrt_mu) * chi.powi(2) * c2
+                - r_0_mag * chi * (1.0 - psi * c3))
+                / r_mag;
+            chi += delta;
+            iterations += 1;
+            if delta.abs() < TOLERANCE * chi.abs().max(1.0) {
+                return Ok(chi);
+            }
+            if iterations == MAX_ITERATIONS || !chi.is_finite() {
+                return Err(OrbitError::NoConvergence {
+                    iterations,
+                    residual: delta.abs(),
+                });
+            }
+        }
+    }
+
+    /* psi, c2, c3 and the radius that go with a value of chi */
+    fn radius(&self, chi: f64) -> (f64, f64, f64, f64) {
+        let psi = chi.powi(2) * self.alpha;
+        let c2 = c2_coeff(psi);
+        let c3 = c3_coeff(psi);
+        let r = chi.powi(2) * c2
+            + (self.rv / self.sqrt_mu) * chi * (1.0 - psi * c3)
+            + self.r_0_mag * (1.0 - psi * c2);
+        (psi, c2, c3, r)
+    }
+
+    /* Starting guess for the universal anomaly depending on the conic type */
+    fn initial_chi(&self, dt: f64) -> f64 {
+        let UniversalPropagator {
+            mu,
+            sqrt_mu,
+            r_0_mag,
+            rv,
+            alpha,
+            ..
+        } = *self;
+        if alpha > ALPHA_PARABOLIC {
+            sqrt_mu * dt * alpha
+        } else if alpha < -ALPHA_PARABOLIC {
+            let a = 1.0 / alpha;
+            let sign = dt.signum();
+            sign * (-a).sqrt()
+                * ((-2.0 * mu * alpha * dt)
+                    / (rv + sign * (-mu * a).sqrt() * (1.0 - r_0_mag * alpha)))
+                    .ln()
+        } else {
+            // Barker's equation gives the parabolic guess
+            let p = self.r_0.cross(&self.v_0).norm_squared() / mu;
+            let s = 0.5 * (PI / 2.0 - (3.0 * (mu / p.powi(3)).sqrt() * dt).atan());
+            let w = s.tan().cbrt().atan();
+            p.sqrt() * 2.0 / (2.0 * w).tan()
+        }
+    }
+}
+
+/// Position and velocity after a time of flight, starting from r_0 and v_0
+/// about a central body with gravitational parameter mu
+pub fn universal_kepler(
+    r_0: Vector3<f64>,
+    v_0: Vector3<f64>,
+    time: f64,
+    mu: f64,
+) -> Result<(Vector3<f64>, Vector3<f64>), OrbitError> {
+    UniversalPropagator::new(r_0, v_0, mu)?.state_at(time)
+}
+
+impl Body {
+    /// Position and velocity at a time in the future, starting from now.
+    /// Works for every orbit type.
+    pub fn state_at_time(&self, time: f64) -> Result<(Vector3<f64>, Vector3<f64>), OrbitError> {
+        universal_kepler(self.position, self.velocity, time, self.mu)
+    }
+
+    /// The body as it will be at a time in the future, starting from now
+    pub fn at_time(&self, time: f64) -> Result<Body, OrbitError> {
+        let (position, velocity) = self.state_at_time(time)?;
+        Ok(Body::new(position, velocity, self.mu))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::body::three_one_three_transform;
+    use crate::constants::SOLARGM;
+    use crate::elements::KeplerianElements;
+    use crate::kepler::{solve_elliptic, solve_hyperbolic, SolverSettings};
+
+    /* Both position and velocity within a relative tolerance */
+    fn assert_state(
+        actual: (Vector3<f64>, Vector3<f64>),
+        expected: (Vector3<f64>, Vector3<f64>),
+        tolerance: f64,
+    ) {
+        let position_error = (actual.0 - expected.0).norm() / expected.0.norm();
+        let velocity_error = (actual.1 - expected.1).norm() / expected.1.norm();
+        assert!(
+            position_error < tolerance,
+            "position off by {:e}",
+            position_error
+        );
+        assert!(
+            velocity_error < tolerance,
+            "velocity off by {:e}",
+            velocity_error
+        );
+    }
+
+    /* True anomaly after time on an ellipse or hyperbola, through the Kepler
+     * solvers */
+    fn kepler_anomaly(elements: &KeplerianElements, time: f64) -> f64 {
+        let e = elements.eccentricity;
+        let a = elements.semi_major_axis;
+        let n = (SOLARGM / a.abs().powi(3)).sqrt();
+        let half_tan = (elements.true_anomaly / 2.0).tan();
+        let settings = SolverSettings::default();
+        if e < 1.0 {
+            let e_0 = 2.0 * (((1.0 - e) / (1.0 + e)).sqrt() * half_tan).atan();
+            let m = e_0 - e * e_0.sin() + n * time;
+            let e_anom = solve_elliptic(m, e, &settings).into_result().unwrap();
+            2.0 * (((1.0 + e) / (1.0 - e)).sqrt() * (e_anom / 2.0).tan()).atan()
+        } else {
+            let h_0 = 2.0 * (((e - 1.0) / (e + 1.0)).sqrt() * half_tan).atanh();
+            let m = e * h_0.sinh() - h_0 + n * time;
+            let h_anom = solve_hyperbolic(m, e, &settings).into_result().unwrap();
+            2.0 * (((e + 1.0) / (e - 1.0)).sqrt() * (h_anom / 2.0).tanh()).atan()
+        }
+    }
+
+    /* State at a true anomaly on a parabola with parameter p (2 q) */
+    fn parabola_state(
+        elements: &KeplerianElements,
+        p: f64,
+        true_anomaly: f64,
+    ) -> (Vector3<f64>, Vector3<f64>) {
+        let radius = p / (1.0 + true_anomaly.cos());
+        let r_p = Vector3::new(
+            radius * true_anomaly.cos(),
+            radius * true_anomaly.sin(),
+            0.0,
+        );
+        let v_p =
+            (SOLARGM / p).sqrt() * Vector3::new(-true_anomaly.sin(), 1.0 + true_anomaly.cos(), 0.0);
+        let trans_mat = three_one_three_transform(
+            elements.argument_of_periapsis,
+            elements.inclination,
+            elements.argument_of_ascending_node,
+        )
+        .transpose();
+        (trans_mat * r_p, trans_mat * v_p)
+    }
+
+    fn check_conic(elements: &KeplerianElements, times: &[f64]) {
+        let body = Body::from_elements(elements, SOLARGM).unwrap();
+        let propagator = UniversalPropagator::new(body.position, body.velocity, SOLARGM).unwrap();
+        for &time in times {
+            let expected = Body::from_elements(
+                &KeplerianElements {
+                    true_anomaly: kepler_anomaly(elements, time),
+                    ..*elements
+                },
+                SOLARGM,
+            )
+            .unwrap();
+            assert_state(
+                propagator.state_at(time).unwrap(),
+                (expected.position, expected.velocity),
+                1e-10,
+            );
+        }
+    }
+
+    #[test]
+    fn ellipse_matches_kepler() {
+        let elements = KeplerianElements::from_degrees(1.3, 0.4, 15.0, 30.0, 80.0, 45.0);
+        let period = 2.0 * PI * (1.3_f64.powi(3) / SOLARGM).sqrt();
+        check_conic(
+            &elements,
+            &[50.0, -120.0, 10.0 * period + 37.0, -3.5 * period],
+        );
+    }
+
+    #[test]
+    fn hyperbola_matches_kepler() {
+        let elements = KeplerianElements::from_degrees(-2.0, 1.5, 20.0, 60.0, 120.0, -30.0);
+        check_conic(&elements, &[30.0, -60.0, 800.0]);
+    }
+
+    #[test]
+    fn parabola_matches_barker() {
+        let elements = KeplerianElements::from_degrees(f64::INFINITY, 1.0, 17.0, 57.0, 115.0, 30.0);
+        let p = 1.6;
+        let start = parabola_state(&elements, p, elements.true_anomaly);
+        let propagator = UniversalPropagator::new(start.0, start.1, SOLARGM).unwrap();
+        for &time in &[40.0, -90.0, 1000.0] {
+            // Barker's equation D + D^3/3 = 2 sqrt(mu/p^3) t with
+            // D = tan(nu/2), solved with Cardano's formula
+            let half_tan = (elements.true_anomaly / 2.0).tan();
+            let b = 1.5 * (half_tan + half_tan.powi(3) / 3.0)
+                + 3.0 * (SOLARGM / p.powi(3)).sqrt() * time;
+            let root = (b + (b.powi(2) + 1.0).sqrt()).cbrt();
+            let true_anomaly = 2.0 * (root - 1.0 / root).atan();
+            assert_state(
+                propagator.state_at(time).unwrap(),
+                parabola_state(&elements, p, true_anomaly),
+                1e-9,
+            );
+        }
+    }
+
+    #[test]
+    fn zero_time_returns_the_start() {
+        let r_0 = Vector3::new(1.0, 0.1, 0.0);
+        let v_0 = Vector3::new(0.0, 0.017, 0.001);
+        let propagator = UniversalPropagator::new(r_0, v_0, SOLARGM).unwrap();
+        assert_eq!(propagator.state_at(0.0).unwrap(), (r_0, v_0));
+    }
+}
@@ -7,7 +7,6 @@
  * Exam 3
  */
 use nalgebra::Vector3;
-const SOLARGM: f64 = 2.963092749241593e-4;
 
 // extern crate colored;
 use colored::*;
@@ -16,43 +15,44 @@ use colored::*;
 use indicatif::{ProgressBar, ProgressStyle};
 
 #[macro_use]
-mod macros;
-mod body;
-mod date;
-use body::Body;
+extern crate astrodynamics;
+use astrodynamics::constants::{NEPTUNEGM, PLUTOGM, SOLARGM};
+use astrodynamics::nbody::{NBody, Particle};
+use astrodynamics::units::{GravParam, Position, Velocity};
+use astrodynamics::{macros, Body, OrbitError};
 
-const DAYTOSEC: f64 = 24.0 * 3600.0;
-
-fn main() {
+fn main() -> Result<(), OrbitError> {
     // Style for progress bars, it's really stupid but I like it
     let style = ProgressStyle::default_bar()
         .template("[{bar:60.cyan/blue}]")
         .progress_chars("#>-");
 
-    let pluto = Body::new(
-        Vector3::new(
+    let pluto = Body::from_state(
+        Position::au(Vector3::new(
             1.218193989126378e1,
             -3.149522235231989e1,
             -1.535562041975234e-1,
-        ),
-        Vector3::new(
+        )),
+        Velocity::au_per_day(Vector3::new(
             3.000627734261702e-3,
             4.635059607321797e-4,
             -9.300258803000724e-4,
-        ),
+        )),
+        GravParam::au3_per_day2(SOLARGM),
     );
 
-    let neptune = Body::new(
-        Vector3::new(
+    let neptune = Body::from_state(
+        Position::au(Vector3::new(
             2.905640909261118e1,
             -7.174984730218214e0,
             -5.218791016710037e-1,
-        ),
-        Vector3::new(
+        )),
+        Velocity::au_per_day(Vector3::new(
             7.317748743401405e-4,
             3.065897473349852e-3,
             -8.039332012516184e-5,
-        ),
+        )),
+        GravParam::au3_per_day2(SOLARGM),
     );
 
     let julian = 2458584.50000;
@@ -66,8 +66,8 @@ fn main() {
     // Day is 70000 and not 0 because I know that pluto passes
     // neptune at least after 70000 days from working this problem before
     let mut day = 70000.0;
-    let mut neptune_radius = neptune.position_at_time(day).norm();
-    let mut pluto_radius = pluto.position_at_time(day).norm();
+    let mut neptune_radius = neptune.position_at_time(day)?.norm();
+    let mut pluto_radius = pluto.position_at_time(day)?.norm();
 
     printer!("\nPluto Radius", s => pluto_radius);
     printer!("Neptune Radius", s => neptune_radius);
@@ -76,8 +76,8 @@ fn main() {
     // neptune
     while neptune_radius < pluto_radius {
         day += 1_f64;
-        neptune_radius = neptune.position_at_time(day).norm();
-        pluto_radius = pluto.position_at_time(day).norm();
+        neptune_radius = neptune.position_at_time(day)?.norm();
+        pluto_radius = pluto.position_at_time(day)?.norm();
     }
 
     let first_date = day + julian;
@@ -87,13 +87,13 @@ fn main() {
      * CALCULATING WHEN PLUTO PASSES NEPTUNE AGAIN
      * ===========================================
      */
-    let mut neptune_radius = neptune.position_at_time(day).norm();
-    let mut pluto_radius = pluto.position_at_time(day).norm();
+    let mut neptune_radius = neptune.position_at_time(day)?.norm();
+    let mut pluto_radius = pluto.position_at_time(day)?.norm();
 
     while neptune_radius > pluto_radius {
         day += 1_f64;
-        neptune_radius = neptune.position_at_time(day).norm();
-        pluto_radius = pluto.position_at_time(day).norm();
+        neptune_radius = neptune.position_at_time(day)?.norm();
+        pluto_radius = pluto.position_at_time(day)?.norm();
     }
 
     let second_date = day + julian;
@@ -111,34 +111,58 @@ fn main() {
     let distance_pb = ProgressBar::new(500 * 365);
     distance_pb.set_style(style.clone());
 
-    // Setting up the problem
+    // Setting up the problem, both planets get propagated over the whole
+    // 500 years at once instead of rebuilding them every day
+    let days = (1..(500 * 365)).map(|i| i as f64);
+    let pluto_path = pluto.propagate(days.clone())?;
+    let neptune_path = neptune.propagate(days)?;
+
     let mut distance = neptune.distance_to(&pluto);
     let mut min_distance = distance;
     let mut min_day = 0;
-    for i in 1..(500 * 365) {
-        let new_pluto = Body::new(
-            pluto.position_at_time(i as f64),
-            pluto.velocity_at_time(i as f64),
-        );
-        let new_neptune = Body::new(
-            neptune.position_at_time(i as f64),
-            neptune.velocity_at_time(i as f64),
-        );
-        distance = new_neptune.distance_to(&new_pluto);
+    for (new_pluto, new_neptune) in pluto_path.iter().zip(&neptune_path) {
+        distance = (new_pluto.position - new_neptune.position).norm();
         if distance < min_distance {
             min_distance = distance;
-            min_day = i;
+            min_day = new_pluto.time as i32;
         }
         distance_pb.inc(1);
     }
     distance_pb.finish_with_message("\nDone!\n");
 
+    /**
+     * ================================================================
+     * SAME CLOSEST APPROACH, BUT WITH THE SUN, NEPTUNE AND PLUTO ALL
+     * PULLING ON EACH OTHER INSTEAD OF EACH PLANET ONLY SEEING THE SUN
+     * ================================================================
+     */
+    let system = NBody::new(vec![
+        Particle::new("Sun", SOLARGM, Vector3::zeros(), Vector3::zeros()),
+        Particle::from_body("Neptune", NEPTUNEGM, &neptune),
+        Particle::from_body("Pluto", PLUTOGM, &pluto),
+    ])
+    .barycentric();
+    let solution = system.integrate(500.0 * 365.0)?;
+    let days = (1..(500 * 365)).map(|i| i as f64);
+    let pluto_path = solution.trajectory(2, 0, days.clone())?;
+    let neptune_path = solution.trajectory(1, 0, days)?;
+
+    let mut nbody_min_distance = neptune.distance_to(&pluto);
+    let mut nbody_min_day = 0;
+    for (new_pluto, new_neptune) in pluto_path.iter().zip(&neptune_path) {
+        distance = (new_pluto.position - new_neptune.position).norm();
+        if distance < nbody_min_distance {
+            nbody_min_distance = distance;
+            nbody_min_day = new_pluto.time as i32;
+        }
+    }
+
     /**
      * ====================================
      * PRINTING OUT RESULTS TO THE TERMINAL
      * ====================================
      */
-    
+
     printer!("First Date", s => first_date);
     printer!("Second Date", s => second_date);
     printer!("Date of Closest Approach", s => julian + min_day as f64);
@@ -159,27 +183,36 @@ fn main() {
     printer!("P", s => pluto.argument_of_periapsis().to_degrees());
     printer!("Q", s => pluto.argument_of_ascending_node().to_degrees());
     printer!("R", s => pluto.true_anomaly().to_degrees());
-    printer!("S-T-U", v => neptune.position_at_time(time));
-    printer!("V-W-X", v => neptune.velocity_at_time(time));
-    printer!("Y-Z-AA", v => pluto.position_at_time(time));
-    printer!("AB-AC-AD", v => pluto.velocity_at_time(time));
+    printer!("S-T-U", v => neptune.position_at_time(time)?);
+    printer!("V-W-X", v => neptune.velocity_at_time(time)?);
+    printer!("Y-Z-AA", v => pluto.position_at_time(time)?);
+    printer!("AB-AC-AD", v => pluto.velocity_at_time(time)?);
     println!(
         "{}\n{}\n",
         macros::underline("AE-AF-AG").cyan(),
         date!(first_date)
     );
-    printer!("AH-AI-AJ", v => neptune.position_at_time(first_date - julian));
-    printer!("AK-AL-AM", v => pluto.position_at_time(first_date - julian));
+    printer!("AH-AI-AJ", v => neptune.position_at_time(first_date - julian)?);
+    printer!("AK-AL-AM", v => pluto.position_at_time(first_date - julian)?);
     println!(
         "{}\n{}\n",
         macros::underline("AN-AO-AP").cyan(),
         date!(second_date)
     );
-    printer!("AQ-AR-AS", v => neptune.position_at_time(second_date - julian));
-    printer!("AT-AU-AV", v => pluto.position_at_time(second_date - julian));
+    printer!("AQ-AR-AS", v => neptune.position_at_time(second_date - julian)?);
+    printer!("AT-AU-AV", v => pluto.position_at_time(second_date - julian)?);
     println!(
         "{}\n{}\n",
         macros::underline("Date of Closest Approach").cyan(),
         date!((min_day as f64) + julian)
     );
+    println!(
+        "{}\n{}\n",
+        macros::underline("N-Body Date of Closest Approach").cyan(),
+        date!((nbody_min_day as f64) + julian)
+    );
+    printer!("Keplerian Closest Distance", s => min_distance);
+    printer!("N-Body Closest Distance", s => nbody_min_distance);
+    println!("{}\n", solution.max_drift());
+    Ok(())
 }
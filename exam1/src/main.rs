@@ -12,39 +12,40 @@ extern crate colored;
 use colored::*;
 
 #[macro_use]
-mod util;
-use self::util::*;
-
-const AUTOM: f64 = 1.49597870700E11;
-const DAYTOSEC: f64 = 86400_f64;
-const TOMETRIC: f64 = AUTOM / DAYTOSEC;
+extern crate astrodynamics;
+use astrodynamics::constants::SOLARGM;
+use astrodynamics::units::{GravParam, Position, Velocity};
+use astrodynamics::{transform, Body};
 
+/* Results are in AU and days, with angles in degrees */
 fn main() {
-    let earth = Body {
-        position: Vector3::new(
+    let earth = Body::from_state(
+        Position::au(Vector3::new(
             -8.461345399508943E-01,
             5.198188201638625E-01,
-            -6.874116231359140E-05,
-        ) * AUTOM,
-        velocity: Vector3::new(
+            -6.87411623135914E-05,
+        )),
+        Velocity::au_per_day(Vector3::new(
             -9.202068150470241E-03,
             -1.477025937149794E-02,
             2.181018061038459E-07,
-        ) * TOMETRIC,
-    };
+        )),
+        GravParam::au3_per_day2(SOLARGM),
+    );
 
-    let venus = Body {
-        position: Vector3::new(
+    let venus = Body::from_state(
+        Position::au(Vector3::new(
             -5.728936053119389E-01,
             -4.341301111844528E-01,
-            2.688719930686330E-02,
-        ) * AUTOM,
-        velocity: Vector3::new(
+            2.68871993068633E-02,
+        )),
+        Velocity::au_per_day(Vector3::new(
             1.221413056525722E-02,
             -1.610029029497521E-02,
             -9.260442405719175E-04,
-        ) * TOMETRIC,
-    };
+        )),
+        GravParam::au3_per_day2(SOLARGM),
+    );
 
     let e_r = earth.position.normalize();
     let e_h = earth.angular_momentum().normalize();
@@ -62,20 +63,20 @@ fn main() {
     let rel_position = trans_mat * (earth.position - venus.position);
     let rel_veloc = trans_mat * earth.relative_velocity(&venus);
 
-    printer!("\nEarth's Total Energy", s => earth.total_energy());
-    printer!("Earth's Angular Momentum", v => earth.angular_momentum());
-    printer!("Earth's Radial Velocity", v => earth.radial_veloc());
-    printer!("Earth's Tangential Velocity", v => earth.tangential_veloc());
-    printer!("Earth's Eccentricity Vector", v => earth.eccentricity_vec());
-    printer!("Earth's True Anomaly", s => earth.true_anomaly());
-    printer!("Frame Rotation Rate", s => earth.rotation_rate());
+    printer!("\nEarth's Total Energy (AU^2/day^2)", s => earth.total_energy());
+    printer!("Earth's Angular Momentum (AU^2/day)", v => earth.angular_momentum());
+    printer!("Earth's Radial Velocity (AU/day)", v => earth.radial_velocity());
+    printer!("Earth's Tangential Velocity (AU/day)", v => earth.tangential_velocity());
+    printer!("Earth's Eccentricity Vector", v => earth.eccentricity_vector());
+    printer!("Earth's True Anomaly (deg)", s => earth.true_anomaly().to_degrees());
+    printer!("Frame Rotation Rate (rad/day)", s => earth.frame_rotation_rate());
     printer!("e_r", v => e_r);
     printer!("e_tht", v => e_tht);
     printer!("e_h", v => e_h);
-    printer!("Venus' Relative Position", v => rel_position);
-    printer!("Venus' Relative Velocity", v => rel_veloc);
-    printer!("Earth-Venus Angle", s => earth.angle_to(&venus));
-    printer!("Venus' Eccentricity Vector", v => venus.eccentricity_vec());
-    printer!("Venus' True Anomaly", s => venus.true_anomaly());
+    printer!("Venus' Relative Position (AU)", v => rel_position);
+    printer!("Venus' Relative Velocity (AU/day)", v => rel_veloc);
+    printer!("Earth-Venus Angle (deg)", s => earth.angle_to(&venus).to_degrees());
+    printer!("Venus' Eccentricity Vector", v => venus.eccentricity_vector());
+    printer!("Venus' True Anomaly (deg)", s => venus.true_anomaly().to_degrees());
     printer!("Transformation Matrix", m => trans_mat);
 }
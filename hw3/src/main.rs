@@ -12,38 +12,34 @@ extern crate colored;
 use colored::*;
 
 #[macro_use]
-mod util;
-use self::util::*;
-
-const METERTOAU: f64 = 1.0 / 1.49597870700e11;
-const SECTODAY: f64 = 1.0 / (24.0 * 3600.0);
-const TODAYSEC: f64 = (24.0 * 3600.0) * (METERTOAU * METERTOAU);
-const KMTOM: f64 = 1000_f64;
+extern crate astrodynamics;
+use astrodynamics::constants::SOLARGM;
+use astrodynamics::units::{GravParam, Position, Velocity};
+use astrodynamics::Body;
 
 fn main() {
-    let mars = Body {
-        position: Vector3::new(
+    let mars = Body::from_state(
+        Position::km(Vector3::new(
             8.535066315950862E+07,
             2.109100603746325E+08,
             2.290206680751368E+06,
-        ) * KMTOM,
-        velocity: Vector3::new(
+        )),
+        Velocity::km_per_s(Vector3::new(
             -2.153469826777515E+01,
             1.121061951236782E+01,
             7.632686135580742E-01,
-        ) * KMTOM,
-    };
+        )),
+        GravParam::au3_per_day2(SOLARGM),
+    );
 
     /* Constructing F frame from mars' orbital parameters */
     let f_frame = mars.make_frame();
-    let z = mars.eccentricity_vec().dot(&mars.angular_momentum()) / mars.angular_momentum().norm();
-    let f_frame_mars = Body {
-        position: f_frame * mars.position,
-        velocity: f_frame * mars.velocity,
-    };
-    let (r0, v0) = f_frame_mars.position_and_velocity(0.0);
-    let (r90, v90) = f_frame_mars.position_and_velocity(90.0);
-    let (r180, v180) = f_frame_mars.position_and_velocity(180.0);
+    let z =
+        mars.eccentricity_vector().dot(&mars.angular_momentum()) / mars.angular_momentum().norm();
+    let f_frame_mars = Body::new(f_frame * mars.position, f_frame * mars.velocity, mars.mu);
+    let (r0, v0) = f_frame_mars.position_and_velocity(0_f64.to_radians());
+    let (r90, v90) = f_frame_mars.position_and_velocity(90_f64.to_radians());
+    let (r180, v180) = f_frame_mars.position_and_velocity(180_f64.to_radians());
 
     printer!("Mars Position", v => mars.position);
     printer!("Mars Velocity", v => mars.velocity);
@@ -53,17 +49,17 @@ fn main() {
     printer!("Mars Angular Momentum", v => mars.angular_momentum());
     printer!("F Frame Angular Momentum", v => f_frame * mars.angular_momentum());
     printer!("Mars Orbital Energy", s => mars.total_energy());
-    printer!("Mars Eccentricty Vector", v => mars.eccentricity_vec());
+    printer!("Mars Eccentricty Vector", v => mars.eccentricity_vector());
     printer!("Z", s => z);
-    printer!("AA", s => mars.eccentricity_vec().dot(&mars.angular_momentum()));
-    printer!("F Frame Eccentricity Vec", s => (f_frame * mars.eccentricity_vec()).norm());
-    printer!("Mars' Semimajor Axis", s => mars.semi_major_axis() * METERTOAU);
-    printer!("Mars' Orbital Parameter", s => mars.orbital_parameter() * METERTOAU);
-    printer!("Mars' True Anomaly", s => f_frame_mars.true_anomaly());
-    printer!("Position at 0 Degrees", v => r0 * METERTOAU);
-    printer!("Velocity at 0 Degrees", v => v0 * TODAYSEC);
-    printer!("Position at 90 Degrees", v => r90 * METERTOAU);
-    printer!("Velocity at 90 Degrees", v => v90 * TODAYSEC);
-    printer!("Position at 180 Degrees", v => r180 * METERTOAU);
-    printer!("Velocity at 180 Degrees", v => v180 * TODAYSEC);
+    printer!("AA", s => mars.eccentricity_vector().dot(&mars.angular_momentum()));
+    printer!("F Frame Eccentricity Vec", s => (f_frame * mars.eccentricity_vector()).norm());
+    printer!("Mars' Semimajor Axis", s => mars.semi_major_axis());
+    printer!("Mars' Orbital Parameter", s => mars.orbital_parameter());
+    printer!("Mars' True Anomaly", s => f_frame_mars.true_anomaly().to_degrees());
+    printer!("Position at 0 Degrees", v => r0);
+    printer!("Velocity at 0 Degrees", v => v0);
+    printer!("Position at 90 Degrees", v => r90);
+    printer!("Velocity at 90 Degrees", v => v90);
+    printer!("Position at 180 Degrees", v => r180);
+    printer!("Velocity at 180 Degrees", v => v180);
 }
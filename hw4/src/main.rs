@@ -12,29 +12,29 @@ extern crate colored;
 use colored::*;
 
 #[macro_use]
-mod util;
-use self::util::*;
+extern crate astrodynamics;
+use astrodynamics::kepler::*;
+use astrodynamics::constants::SOLARGM;
+use astrodynamics::units::{GravParam, Position, Velocity};
+use astrodynamics::{Body, OrbitError};
 use std::f64::consts::PI;
 
-const METERTOAU: f64 = 1.0 / 1.49597870700e11;
-const SECTODAY: f64 = 1.0 / (24.0 * 3600.0);
-const TODAYSEC: f64 = (24.0 * 3600.0) * (METERTOAU * METERTOAU);
-const KMTOM: f64 = 1000_f64;
-const DAYTOSEC: f64 = 24.0 * 3600.0;
-
-fn main() {
-    let ryugu = Body {
-        position: Vector3::new(
+/* Results are in AU and days; anomalies are in radians unless marked
+ * as degrees */
+fn main() -> Result<(), OrbitError> {
+    let ryugu = Body::from_state(
+        Position::km(Vector3::new(
             1.132759321672478E+08,
             -1.733831194873283E+08,
             1.676906176312257E+07,
-        ),
-        velocity: Vector3::new(
+        )),
+        Velocity::km_per_s(Vector3::new(
             1.804598259087825E+01,
-            1.405146782685940E+01,
-            1.309063084989150E+00,
-        ),
-    };
+            1.40514678268594E+01,
+            1.30906308498915E+00,
+        )),
+        GravParam::au3_per_day2(SOLARGM),
+    );
 
     /* PROBLEM 1a */
     let mut nt = PI / 3.0;
@@ -42,47 +42,51 @@ fn main() {
 
     printer!("\nA", s => -delta_e(0.0, nt, eccen));
 
-    let b = kepler(0.0, nt, eccen);
+    let b = kepler_step(0.0, nt, eccen);
     printer!("B", s => b);
     printer!("C", s => -delta_e(b, nt, eccen));
-    printer!("D", s => kepler_iterate(b, nt, eccen));
+    printer!("D", s => kepler_iterate(b, nt, eccen)?);
 
     /* PROBLEM 1a v. */
     nt = (17.0 * PI) / 4.0;
     printer!("E", s => -delta_e(0.0, nt, eccen));
 
-    let f = kepler(0.0, nt, eccen);
+    let f = kepler_step(0.0, nt, eccen);
     printer!("F", s => f);
     printer!("G", s => -delta_e(f, nt, eccen));
-    printer!("H", s => kepler_iterate(f, nt, eccen));
+    printer!("H", s => kepler_iterate(f, nt, eccen)?);
 
     /* PROBLEM 1b */
     nt = (3.0 * PI) / 4.0;
     eccen = 1.73;
     printer!("I", s => -hyper_delta_e(0.0, nt, eccen));
 
-    let j = hyper_kepler(0.0, nt, eccen);
+    let j = hyper_kepler_step(0.0, nt, eccen);
     printer!("J", s => j);
     printer!("K", s => -hyper_delta_e(j, nt, eccen));
-    printer!("L", s => hyper_kepler_iterate(j, nt, eccen));
+    printer!("L", s => hyper_kepler_iterate(j, nt, eccen)?);
 
     nt = (13.0 * PI) / 3.0;
     printer!("M", s => -hyper_delta_e(0.0, nt, eccen));
-    let n = hyper_kepler(0.0, nt, eccen);
+    let n = hyper_kepler_step(0.0, nt, eccen);
     printer!("N", s => n);
-    printer!("O", s => hyper_kepler_iterate(n, nt, eccen));
+    printer!("O", s => hyper_kepler_iterate(n, nt, eccen)?);
 
     nt = 76.0 * PI;
-    printer!("P", s => hyper_kepler_iterate(0.0, nt, eccen));
+    // Newton's method from zero overshoots and never settles here
+    match hyper_kepler_iterate(0.0, nt, eccen) {
+        Ok(p) => printer!("P", s => p),
+        Err(error) => println!("{}:\n  {}\n", "P".green(), error),
+    }
 
     nt = 194.0 * PI;
-    let q = hyper_kepler_iterate(5.0, nt, eccen);
+    let q = hyper_kepler_iterate(5.0, nt, eccen)?;
     printer!("Q", s => q);
 
-    let eccen_anom = ryugu.eccen_anom_at_time(143.0 * DAYTOSEC).to_radians();
+    let eccen_anom = ryugu.eccentric_anomaly_at_time(143.0)?;
 
     let sma = ryugu.semi_major_axis().to_radians();
-    let e_vec = ryugu.eccentricity_vec().normalize();
+    let e_vec = ryugu.eccentricity_vector().normalize();
 
     let x = (sma * eccen_anom.cos()) - (sma * e_vec.norm());
     let y = sma * (1.0 - e_vec.norm().powi(2)).sqrt() * eccen_anom.sin();
@@ -95,12 +99,13 @@ fn main() {
     /**
      * The rest of the homework
      */
-    printer!("Ryugu's Eccentricity Vector", v => ryugu.eccentricity_vec());
-    printer!("Ryugu's Semi-Major Axis", s => ryugu.semi_major_axis());
-    printer!("Ryugu's True Anomaly", s => ryugu.true_anomaly());
-    printer!("Ryugu's Orbital Period", s => ryugu.orbital_period() * SECTODAY);
-    printer!("Ryugu's Eccentric Anomaly", s => ryugu.eccentric_anomaly());
-    printer!("Time Since Periapsis", s => ryugu.time_since_periapsis() * SECTODAY);
-    printer!("Eccentric Anomaly (143 days)", s => eccen_anom);
+    printer!("Ryugu's Eccentricity Vector", v => ryugu.eccentricity_vector());
+    printer!("Ryugu's Semi-Major Axis (AU)", s => ryugu.semi_major_axis());
+    printer!("Ryugu's True Anomaly (deg)", s => ryugu.true_anomaly().to_degrees());
+    printer!("Ryugu's Orbital Period (days)", s => ryugu.orbital_period());
+    printer!("Ryugu's Eccentric Anomaly (deg)", s => ryugu.eccentric_anomaly().to_degrees());
+    printer!("Time Since Periapsis (days)", s => ryugu.time_since_periapsis());
+    printer!("Eccentric Anomaly at 143 days (rad)", s => eccen_anom);
     printer!("Last Problem", v => radius);
+    Ok(())
 }
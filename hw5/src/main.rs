@@ -6,39 +6,38 @@
  * ENGR 3310-002
  */
 use nalgebra::Vector3;
-const SOLARGM: f64 = 1.328905188132376e11;
 
 // extern crate colored;
 use colored::*;
 
 #[macro_use]
-mod macros;
-mod body;
-mod date;
-use body::{Body, OrbitType};
+extern crate astrodynamics;
+use astrodynamics::constants::SOLARGM;
+use astrodynamics::units::{GravParam, Position, Velocity};
+use astrodynamics::{body, macros, Body, OrbitError};
 
-const DAYTOSEC: f64 = 24.0 * 3600.0;
-
-fn main() {
-    let earth = Body::new(
-        Vector3::new(
+/* Results are in AU and days, with angles in degrees */
+fn main() -> Result<(), OrbitError> {
+    let earth = Body::from_state(
+        Position::km(Vector3::new(
             -1.491581119145494E+08,
-            -5.727627782875820E+06,
+            -5.72762778287582E+06,
             -5.679400441655191E+03,
-        ),
-        Vector3::new(
-            8.635360877981350E-01,
+        )),
+        Velocity::km_per_s(Vector3::new(
+            8.63536087798135E-01,
             -2.985696666561909E+01,
             1.972889032860081E-03,
-        ),
+        )),
+        GravParam::au3_per_day2(SOLARGM),
     );
 
     let time = 10000.35615;
 
-    let current_julian = date!(2019-03-23 20:00:00);
+    let current_julian = date!(2019-3-23 20:00:00);
     let new_julian = current_julian + time;
     let greg_date = date!(new_julian);
-    let t_anom = earth.true_anomaly_at_time(time);
+    let t_anom = earth.true_anomaly_at_time(time)?;
     let trans_mat = body::three_one_three_transform(
         t_anom + earth.argument_of_periapsis(),
         earth.inclination(),
@@ -47,22 +46,26 @@ fn main() {
     .try_inverse()
     .unwrap();
 
-    let p = earth.orbital_parameter() / (1.0 + earth.eccentricity() * t_anom.cos());
-    let v = (.sqrt();
+    // Radial and transverse components in the rotating frame
+    let e = earth.eccentricity();
+    let p = earth.orbital_parameter();
+    let radius = p / (1.0 + e * t_anom.cos());
+    let v = (earth.mu / p).sqrt();
 
-    let r_B = Vector3::new(p, 0.0, 0.0);
-    let v_B = Vector3::new(v * t_anom.cos(), v * t_anom.sin(), 0.0);
+    let r_b = Vector3::new(radius, 0.0, 0.0);
+    let v_b = Vector3::new(v * e * t_anom.sin(), v * (1.0 + e * t_anom.cos()), 0.0);
 
-    printer!("A-B-C", v => earth.position);
-    printer!("D-E-F", v => earth.velocity);
-    printer!("G", s => earth.semi_major_axis());
+    printer!("A-B-C (AU)", v => earth.position);
+    printer!("D-E-F (AU/day)", v => earth.velocity);
+    printer!("G (AU)", s => earth.semi_major_axis());
     printer!("H", s => earth.eccentricity());
-    printer!("I", s => earth.inclination().to_degrees());
-    printer!("J", s => earth.argument_of_periapsis().to_degrees());
-    printer!("K", s => earth.argument_of_ascending_node().to_degrees());
-    printer!("L", s => t_anom.to_degrees());
+    printer!("I (deg)", s => earth.inclination().to_degrees());
+    printer!("J (deg)", s => earth.argument_of_periapsis().to_degrees());
+    printer!("K (deg)", s => earth.argument_of_ascending_node().to_degrees());
+    printer!("L (deg)", s => t_anom.to_degrees());
     println!("{}\n{}\n", macros::underline("Problem 8").cyan(), greg_date);
     printer!("Problem 9", m => trans_mat);
-    printer!("Position", v => trans_mat * r_B);
-    printer!("Velocity", v => trans_mat * v_B);
+    printer!("Position (AU)", v => trans_mat * r_b);
+    printer!("Velocity (AU/day)", v => trans_mat * v_b);
+    Ok(())
 }
@@ -0,0 +1,78 @@
+/**
+ * Austen LeBeau
+ *
+ * Porkchop plot for an Earth to Mars transfer around the 2020 opportunity,
+ * using the Earth and Mars states from the final. Writes porkchop.csv with
+ * every transfer on the grid and porkchop.svg with C3 contours, then prints
+ * the cheapest launch it found.
+ */
+use nalgebra::Vector3;
+use std::fs::File;
+use std::io::BufWriter;
+
+use colored::*;
+
+#[macro_use]
+extern crate astrodynamics;
+use astrodynamics::constants::SOLARGM;
+use astrodynamics::porkchop::{DateRange, Ephemeris, Porkchop, Quantity};
+use astrodynamics::units::{GravParam, Position, Velocity};
+use astrodynamics::Body;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let epoch = date!(2019-4-22 00:00:00);
+
+    let earth = Body::from_state(
+        Position::au(Vector3::new(
+            -8.601208063143356e-1,
+            -5.149780543253561e-1,
+            -7.874942568326342e-6,
+        )),
+        Velocity::au_per_day(Vector3::new(
+            8.657167839482775e-3,
+            -1.475977839094277e-2,
+            8.628767650706375e-7,
+        )),
+        GravParam::au3_per_day2(SOLARGM),
+    );
+
+    let mars = Body::from_state(
+        Position::au(Vector3::new(
+            -2.622004974599996e-1,
+            1.577561813000981,
+            3.925526885339471e-2,
+        )),
+        Velocity::au_per_day(Vector3::new(
+            -1.328446880856093e-2,
+            -1.101073656563134e-3,
+            3.02880366477932e-4,
+        )),
+        GravParam::au3_per_day2(SOLARGM),
+    );
+
+    let departures = DateRange::new(date!(2020-5-1 00:00:00), date!(2020-9-30 00:00:00), 2.0);
+    let arrivals = DateRange::new(date!(2020-11-1 00:00:00), date!(2021-10-1 00:00:00), 2.0);
+
+    let porkchop = Porkchop::new(
+        &Ephemeris::new(earth, epoch),
+        &Ephemeris::new(mars, epoch),
+        &departures,
+        &arrivals,
+    )?;
+
+    porkchop.write_csv(BufWriter::new(File::create("porkchop.csv")?))?;
+    porkchop.write_svg(
+        BufWriter::new(File::create("porkchop.svg")?),
+        Quantity::C3,
+        &[10.0, 12.0, 15.0, 20.0, 25.0, 30.0, 40.0, 50.0],
+    )?;
+
+    if let Some(best) = porkchop.best(Quantity::C3) {
+        printer!("Lowest C3", s => best.c3);
+        printer!("Departure v-inf", s => best.v_inf_departure);
+        printer!("Arrival v-inf", s => best.v_inf_arrival);
+        println!("Departure\n{}\n", date!(best.departure));
+        println!("Arrival\n{}\n", date!(best.arrival));
+    }
+    Ok(())
+}